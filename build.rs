@@ -0,0 +1,78 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+// backend crates whose resolved version is exposed to the verifiers, the
+// version prefix tells the side by side risc0-zkvm releases apart
+const BACKEND_CRATES: &[(&str, &str, &str)] = &[
+    ("SP1_CORE_VERSION", "sp1-core", "0."),
+    ("SP1_SDK_VERSION", "sp1-sdk", "2."),
+    ("MIDEN_VM_VERSION", "miden-vm", ""),
+    ("RISC0_ZKVM_VERSION", "risc0-zkvm", "0."),
+    ("RISC0_ZKVM_1_VERSION", "risc0-zkvm", "1."),
+    ("JOLT_SDK_VERSION", "jolt-sdk", ""),
+    ("ARK_GROTH16_VERSION", "ark-groth16", ""),
+    ("PLONKY2_VERSION", "plonky2", ""),
+    ("HALO2_PROOFS_VERSION", "halo2_proofs", ""),
+];
+
+struct LockedPackage {
+    name: String,
+    version: String,
+    source: String,
+}
+
+fn quoted(line: &str, key: &str) -> Option<String> {
+    let value = line.strip_prefix(key)?.trim().strip_prefix('=')?.trim();
+    Some(value.trim_matches('"').to_string())
+}
+
+fn locked_packages(lock: &str) -> Vec<LockedPackage> {
+    let mut packages = Vec::new();
+    for block in lock.split("[[package]]").skip(1) {
+        let mut package = LockedPackage {
+            name: String::new(),
+            version: String::new(),
+            source: String::new(),
+        };
+        for line in block.lines() {
+            if let Some(name) = quoted(line, "name") {
+                package.name = name;
+            } else if let Some(version) = quoted(line, "version") {
+                package.version = version;
+            } else if let Some(source) = quoted(line, "source") {
+                package.source = source;
+            }
+        }
+        packages.push(package);
+    }
+    packages
+}
+
+// registry crates are known by their version, git checkouts by their commit
+fn backend_version(package: &LockedPackage) -> String {
+    match package.source.strip_prefix("git+") {
+        Some(source) => match source.rsplit_once('#') {
+            Some((_, commit)) => commit.chars().take(7).collect(),
+            None => package.version.clone(),
+        },
+        None => package.version.clone(),
+    }
+}
+
+fn main() {
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let lock_file = Path::new(&manifest_dir).join("Cargo.lock");
+    println!("cargo:rerun-if-changed={}", lock_file.display());
+    let packages = fs::read_to_string(&lock_file)
+        .map(|lock| locked_packages(&lock))
+        .unwrap_or_default();
+    for (variable, name, prefix) in BACKEND_CRATES {
+        let version = packages
+            .iter()
+            .find(|package| package.name == *name && package.version.starts_with(prefix))
+            .map(backend_version)
+            .unwrap_or_else(|| "unknown".to_string());
+        println!("cargo:rustc-env={variable}={version}");
+    }
+}
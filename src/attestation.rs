@@ -0,0 +1,310 @@
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use k256::ecdsa::SigningKey as EvmSigningKey;
+use lazy_static::lazy_static;
+use log::{info, warn};
+use rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use sha3::Keccak256;
+use std::fs;
+use std::path::Path;
+
+use crate::config::Config;
+use crate::errors::VerificationError;
+use crate::models::{
+    Attestation, EvmAttestation, VerificationJob, VerificationOutcome, VerificationResult,
+};
+use crate::quarantine::sha256_file;
+
+pub const ATTESTATION_DOMAIN: &str = "jugalbandi-attestation-v1";
+
+lazy_static! {
+    static ref SIGNING_KEY: SigningKey =
+        SigningKey::from_bytes(&load_or_generate(&Config::init().attestation_key_file));
+    static ref EVM_SIGNING_KEY: EvmSigningKey = EvmSigningKey::from_bytes(
+        &load_or_generate(&Config::init().evm_attestation_key_file).into()
+    )
+    .expect("EVM_ATTESTATION_KEY_FILE must hold a valid secp256k1 secret key");
+}
+
+// the key file holds a hex encoded 32 byte secret
+fn read_key(key_file: &Path) -> std::io::Result<[u8; 32]> {
+    let seed_hex = fs::read_to_string(key_file)?;
+    hex::decode(seed_hex.trim())
+        .ok()
+        .and_then(|seed| seed.try_into().ok())
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "{} must hold a hex encoded 32 byte secret",
+                    key_file.display()
+                ),
+            )
+        })
+}
+
+// a missing key file is created with a fresh secret so restarts keep signing
+// with the same key
+fn load_or_generate(key_file: &Path) -> [u8; 32] {
+    match read_key(key_file) {
+        Ok(seed) => seed,
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => panic!("{err}"),
+        Err(_) => {
+            let mut seed = [0u8; 32];
+            OsRng.fill_bytes(&mut seed);
+            write_key_file(key_file, &hex::encode(seed)).expect("Error writing attestation key");
+            info!("Generated attestation key {}", key_file.display());
+            seed
+        }
+    }
+}
+
+fn write_key_file(key_file: &Path, seed_hex: &str) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    if let Some(parent) = key_file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(key_file)?;
+    file.write_all(seed_hex.as_bytes())
+}
+
+pub fn public_key() -> String {
+    hex::encode(SIGNING_KEY.verifying_key().to_bytes())
+}
+
+// the public key of an existing key file, for tools that only check
+// signatures and must not create a key when there is none
+pub fn read_public_key(key_file: &Path) -> std::io::Result<String> {
+    let seed = read_key(key_file)?;
+    Ok(hex::encode(
+        SigningKey::from_bytes(&seed).verifying_key().to_bytes(),
+    ))
+}
+
+pub fn sign(message: &[u8]) -> String {
+    hex::encode(SIGNING_KEY.sign(message).to_bytes())
+}
+
+// checks a hex encoded signature made by another instance's key
+pub fn verify_signature(public_key: &str, message: &[u8], signature: &str) -> bool {
+    let public_key: Option<[u8; 32]> = hex::decode(public_key)
+        .ok()
+        .and_then(|public_key| public_key.try_into().ok());
+    let signature: Option<[u8; 64]> = hex::decode(signature)
+        .ok()
+        .and_then(|signature| signature.try_into().ok());
+    match (public_key, signature) {
+        (Some(public_key), Some(signature)) => VerifyingKey::from_bytes(&public_key)
+            .map(|public_key| {
+                public_key
+                    .verify_strict(message, &Signature::from_bytes(&signature))
+                    .is_ok()
+            })
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+// the signed message is the compact JSON array [domain, tx_id, backend,
+// program_id, proof_hash, public_outputs_hash, is_valid, result, timestamp]
+// with null for a missing program id or proof hash
+pub fn message(attestation: &Attestation) -> Vec<u8> {
+    serde_json::to_vec(&(
+        ATTESTATION_DOMAIN,
+        &attestation.tx_id,
+        &attestation.backend,
+        &attestation.program_id,
+        &attestation.proof_hash,
+        &attestation.public_outputs_hash,
+        attestation.is_valid,
+        attestation.result,
+        attestation.timestamp,
+    ))
+    .unwrap_or_default()
+}
+
+fn hash_file(file_path: &str) -> Option<String> {
+    match sha256_file(Path::new(file_path)) {
+        Ok((sha256, _)) => Some(sha256),
+        Err(err) => {
+            warn!("Error hashing {} for attestation: {:?}", file_path, err);
+            None
+        }
+    }
+}
+
+// outputs the verifier extracted, or else the ones the submission claimed
+fn public_outputs_hash(
+    job: Option<&VerificationJob>,
+    verification_result: &Result<VerificationResult, VerificationError>,
+) -> String {
+    let outputs = match verification_result {
+        Ok(VerificationResult {
+            public_inputs: Some(public_inputs),
+            ..
+        }) => public_inputs.clone(),
+        _ => job.map(VerificationJob::public_outputs).unwrap_or_default(),
+    };
+    hex::encode(Sha256::digest(
+        serde_json::to_vec(&outputs).unwrap_or_default(),
+    ))
+}
+
+// signs the outcome of a verification, called before the proof files are
+// released so they can still be hashed. With batching enabled the attestation
+// is left unsigned and covered by the signed root of its batch instead
+pub fn attest(
+    tx_id: &str,
+    backend: &str,
+    job: Option<&VerificationJob>,
+    verification_result: &Result<VerificationResult, VerificationError>,
+    timestamp: u64,
+) -> Attestation {
+    let program_id = job.and_then(|job| {
+        job.program_id()
+            .or_else(|| job.program_file().and_then(hash_file))
+    });
+    let is_valid = matches!(verification_result, Ok(result) if result.is_valid);
+    let mut attestation = Attestation {
+        tx_id: tx_id.to_string(),
+        backend: backend.to_string(),
+        program_id,
+        proof_hash: job.and_then(|job| hash_file(job.proof_file())),
+        public_outputs_hash: public_outputs_hash(job, verification_result),
+        is_valid,
+        result: VerificationOutcome::from_valid(is_valid),
+        timestamp,
+        public_key: public_key(),
+        signature: None,
+    };
+    if Config::init().batch_interval_secs == 0 {
+        attestation.signature = Some(sign(&message(&attestation)));
+    }
+    attestation
+}
+
+// the attestation re-signed for the outcome a quorum settled on, which is
+// what gets recorded in place of this instance's own verdict
+pub fn with_outcome(attestation: &Attestation, outcome: VerificationOutcome) -> Attestation {
+    let mut attestation = Attestation {
+        is_valid: outcome == VerificationOutcome::Valid,
+        result: outcome,
+        signature: None,
+        ..attestation.clone()
+    };
+    if Config::init().batch_interval_secs == 0 {
+        attestation.signature = Some(sign(&message(&attestation)));
+    }
+    attestation
+}
+
+pub fn keccak256(bytes: &[u8]) -> [u8; 32] {
+    Keccak256::digest(bytes).into()
+}
+
+// a sha256 hex digest as bytes32, zero when it is missing
+fn hash_word(hash: Option<&String>) -> [u8; 32] {
+    hash.and_then(|hash| hex::decode(hash).ok())
+        .and_then(|hash| hash.try_into().ok())
+        .unwrap_or_default()
+}
+
+pub fn uint_word(value: u64) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[24..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+pub fn evm_address() -> String {
+    let public_key = EVM_SIGNING_KEY.verifying_key().to_encoded_point(false);
+    let hash = keccak256(&public_key.as_bytes()[1..]);
+    format!("0x{}", hex::encode(&hash[12..]))
+}
+
+// abi.encode(bytes32 domain, bytes32 txId, bytes32 backend, bytes32 programId,
+//     bytes32 proofHash, bytes32 publicOutputsHash, bool isValid, uint8 result,
+//     uint64 timestamp)
+// with strings passed through keccak256, the same layout a contract rebuilds
+// before hashing it and calling ecrecover on the EIP-191 digest
+pub fn evm_encode(attestation: &Attestation) -> Vec<u8> {
+    let words = [
+        keccak256(ATTESTATION_DOMAIN.as_bytes()),
+        keccak256(attestation.tx_id.as_bytes()),
+        keccak256(attestation.backend.as_bytes()),
+        keccak256(
+            attestation
+                .program_id
+                .as_deref()
+                .unwrap_or_default()
+                .as_bytes(),
+        ),
+        hash_word(attestation.proof_hash.as_ref()),
+        hash_word(Some(&attestation.public_outputs_hash)),
+        uint_word(attestation.is_valid as u64),
+        uint_word(attestation.result.code() as u64),
+        uint_word(attestation.timestamp),
+    ];
+    words.concat()
+}
+
+// signs the ABI encoded words as an EIP-191 personal message, the signature
+// is r || s || v with v as 27 or 28
+pub fn sign_evm(encoded: &[u8]) -> Result<EvmAttestation, VerificationError> {
+    let message_hash = keccak256(encoded);
+    let digest = keccak256(
+        &[
+            b"\x19Ethereum Signed Message:\n32".as_slice(),
+            message_hash.as_slice(),
+        ]
+        .concat(),
+    );
+    let (signature, recovery_id) = EVM_SIGNING_KEY
+        .sign_prehash_recoverable(&digest)
+        .map_err(|err| VerificationError::KeyError(format!("Error signing attestation: {err}")))?;
+    let mut signature_bytes = signature.to_bytes().to_vec();
+    signature_bytes.push(27 + recovery_id.to_byte());
+    Ok(EvmAttestation {
+        signer: evm_address(),
+        message_hash: format!("0x{}", hex::encode(message_hash)),
+        digest: format!("0x{}", hex::encode(digest)),
+        signature: format!("0x{}", hex::encode(signature_bytes)),
+    })
+}
+
+pub fn attest_evm(attestation: &Attestation) -> Result<EvmAttestation, VerificationError> {
+    sign_evm(&evm_encode(attestation))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_a_public_key_without_creating_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_file = dir.path().join("attestation.key");
+        let err = read_public_key(&key_file).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+        assert!(!key_file.exists());
+
+        fs::write(&key_file, "not hex").unwrap();
+        let err = read_public_key(&key_file).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        fs::write(&key_file, format!("{}\n", hex::encode([7u8; 32]))).unwrap();
+        assert_eq!(
+            read_public_key(&key_file).unwrap(),
+            hex::encode(
+                SigningKey::from_bytes(&[7u8; 32])
+                    .verifying_key()
+                    .to_bytes()
+            )
+        );
+    }
+}
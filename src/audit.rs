@@ -0,0 +1,722 @@
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::attestation;
+use crate::config::Config;
+use crate::errors::VerificationError;
+use crate::models::{VerificationOutcome, VerificationRecord, VerificationResult};
+use crate::quarantine::sha256_file;
+use crate::retention::unix_timestamp;
+
+pub const AUDIT_VERIFY_ARG: &str = "--audit-verify";
+pub const AUDIT_EXPORT_ARG: &str = "--audit-export";
+pub const AUDIT_HEAD_DOMAIN: &str = "jugalbandi-audit-head-v1";
+
+// the prev_hash of the first entry
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InputHash {
+    pub path: String,
+    pub sha256: Option<String>,
+}
+
+// one decision of the worker, written as a JSON line
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AuditEntry {
+    pub seq: u64,
+    pub tx_id: String,
+    pub verify_type: u8,
+    pub backend: String,
+    pub inputs: Vec<InputHash>,
+    pub is_valid: bool,
+    pub result: VerificationOutcome,
+    pub error: Option<String>,
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl AuditEntry {
+    // sha256 over the compact JSON of every field but the hash itself, in
+    // declaration order
+    pub fn compute_hash(&self) -> String {
+        let body = serde_json::to_vec(&(
+            self.seq,
+            &self.tx_id,
+            self.verify_type,
+            &self.backend,
+            &self.inputs,
+            self.is_valid,
+            self.result,
+            &self.error,
+            self.started_at,
+            self.finished_at,
+            &self.prev_hash,
+        ))
+        .unwrap_or_default();
+        hex::encode(Sha256::digest(body))
+    }
+}
+
+// the seq and hash of the last entry signed with the attestation key, kept
+// next to the log as <file>.head. Rewriting any entry changes every later
+// hash, so the chain can't be recomputed without the key
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SignedHead {
+    pub seq: u64,
+    pub hash: String,
+    pub signed_at: u64,
+    pub public_key: String,
+    pub signature: String,
+}
+
+// the signed message is the compact JSON array [domain, seq, hash, signed_at]
+fn head_message(head: &SignedHead) -> Vec<u8> {
+    serde_json::to_vec(&(AUDIT_HEAD_DOMAIN, head.seq, &head.hash, head.signed_at))
+        .unwrap_or_default()
+}
+
+fn head_file(path: &Path) -> PathBuf {
+    let mut head_file = path.as_os_str().to_owned();
+    head_file.push(".head");
+    PathBuf::from(head_file)
+}
+
+// a missing head is None, like a missing log
+pub fn read_head(path: &Path) -> std::io::Result<Option<SignedHead>> {
+    match fs::read(head_file(path)) {
+        Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+// written to a temporary file first so a crash never leaves half a head
+fn write_head(path: &Path, seq: u64, hash: &str) -> std::io::Result<SignedHead> {
+    let mut head = SignedHead {
+        seq,
+        hash: hash.to_string(),
+        signed_at: unix_timestamp(),
+        public_key: attestation::public_key(),
+        signature: String::new(),
+    };
+    head.signature = attestation::sign(&head_message(&head));
+    let head_path = head_file(path);
+    let mut tmp_path = head_path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let mut file = fs::File::create(&tmp_path)?;
+    file.write_all(&serde_json::to_vec(&head)?)?;
+    file.sync_data()?;
+    fs::rename(tmp_path, head_path)?;
+    Ok(head)
+}
+
+#[derive(Serialize, Debug)]
+pub struct ChainReport {
+    pub entries: u64,
+    pub is_valid: bool,
+    pub last_hash: String,
+    // seq (or line number for unparsable lines) of the first broken entry
+    pub broken_at: Option<u64>,
+    pub error: Option<String>,
+    // seq of the entry the signed head was checked against
+    pub signed_head: Option<u64>,
+}
+
+// a crash in the middle of an append leaves a final line without its
+// newline, it is cut off so the chain ends at the last complete entry
+fn truncate_torn_tail(path: &Path) -> std::io::Result<()> {
+    let mut file = match OpenOptions::new().read(true).write(true).open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+    let len = file.metadata()?.len();
+    let mut end = len;
+    let mut buffer = [0u8; 4096];
+    while end > 0 {
+        let start = end.saturating_sub(buffer.len() as u64);
+        let chunk = &mut buffer[..(end - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(chunk)?;
+        if let Some(newline) = chunk.iter().rposition(|byte| *byte == b'\n') {
+            end = start + newline as u64 + 1;
+            break;
+        }
+        end = start;
+    }
+    if end < len {
+        warn!(
+            "Truncating the torn last {} bytes of audit log {}",
+            len - end,
+            path.display()
+        );
+        file.set_len(end)?;
+        file.sync_data()?;
+    }
+    Ok(())
+}
+
+// the tail of the chain, kept by the worker so appends do not reread the file
+pub struct AuditLog {
+    path: PathBuf,
+    next_seq: u64,
+    last_hash: String,
+}
+
+impl AuditLog {
+    // a broken chain is an error, appending after it would leave every new
+    // entry unverifiable
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        truncate_torn_tail(path)?;
+        let head = read_head(path)?;
+        let mut report = verify_entries(path, head.as_ref());
+        match &head {
+            Some(head) => verify_head(&mut report, head, &attestation::public_key()),
+            None if report.entries > 0 => warn!(
+                "Audit log {} has no signed head, signing its last entry",
+                path.display()
+            ),
+            None => {}
+        }
+        if !report.is_valid {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "audit log {} is broken at {:?}: {}, inspect it with {} and move it aside \
+                     to start a new log",
+                    path.display(),
+                    report.broken_at,
+                    report.error.unwrap_or_default(),
+                    AUDIT_VERIFY_ARG
+                ),
+            ));
+        }
+        if head.is_none() && report.entries > 0 {
+            write_head(path, report.entries - 1, &report.last_hash)?;
+        }
+        info!(
+            "Audit log {} has {} entries",
+            path.display(),
+            report.entries
+        );
+        Ok(AuditLog {
+            path: path.to_path_buf(),
+            next_seq: report.entries,
+            last_hash: report.last_hash,
+        })
+    }
+
+    // the outcome is the one the quorum settled on in coordinator mode, the
+    // error is this instance's own
+    pub fn append(
+        &mut self,
+        record: &VerificationRecord,
+        verification_result: &Result<VerificationResult, VerificationError>,
+        outcome: VerificationOutcome,
+    ) -> std::io::Result<AuditEntry> {
+        let inputs = record
+            .files
+            .iter()
+            .map(|path| InputHash {
+                path: path.clone(),
+                sha256: sha256_file(Path::new(path)).ok().map(|(sha256, _)| sha256),
+            })
+            .collect();
+        let mut entry = AuditEntry {
+            seq: self.next_seq,
+            tx_id: record.tx_id.clone(),
+            verify_type: record.verify_type,
+            backend: record.backend.clone(),
+            inputs,
+            is_valid: outcome == VerificationOutcome::Valid,
+            result: outcome,
+            error: verification_result
+                .as_ref()
+                .err()
+                .map(|err| err.to_string()),
+            started_at: record.started_at,
+            finished_at: record.finished_at,
+            prev_hash: self.last_hash.clone(),
+            hash: String::new(),
+        };
+        entry.hash = entry.compute_hash();
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        file.write_all(&line)?;
+        file.sync_data()?;
+
+        self.next_seq += 1;
+        self.last_hash = entry.hash.clone();
+        write_head(&self.path, entry.seq, &entry.hash)?;
+        Ok(entry)
+    }
+}
+
+fn read_entries(path: &Path) -> std::io::Result<impl Iterator<Item = std::io::Result<String>>> {
+    let file = fs::File::open(path)?;
+    Ok(BufReader::new(file).lines())
+}
+
+// checks the chain and its signed head against the given public key, a
+// chain with entries but no head is broken
+pub fn verify_chain(path: &Path, public_key: &str) -> ChainReport {
+    let head = match read_head(path) {
+        Ok(head) => head,
+        Err(err) => {
+            let mut report = verify_entries(path, None);
+            if report.is_valid {
+                report.is_valid = false;
+                report.error = Some(format!("Error reading signed head: {err}"));
+            }
+            return report;
+        }
+    };
+    let mut report = verify_entries(path, head.as_ref());
+    match &head {
+        Some(head) => verify_head(&mut report, head, public_key),
+        None if report.is_valid && report.entries > 0 => {
+            report.is_valid = false;
+            report.error = Some("the chain head is not signed".to_string());
+        }
+        None => {}
+    }
+    report
+}
+
+fn verify_head(report: &mut ChainReport, head: &SignedHead, public_key: &str) {
+    if !report.is_valid {
+        return;
+    }
+    let problem = if head.public_key != public_key {
+        Some("the chain head is signed by another key")
+    } else if !attestation::verify_signature(public_key, &head_message(head), &head.signature) {
+        Some("the chain head signature is not valid")
+    } else if head.seq >= report.entries {
+        Some("the chain ends before its signed head")
+    } else {
+        None
+    };
+    match problem {
+        Some(problem) => {
+            report.is_valid = false;
+            report.broken_at = Some(report.entries.min(head.seq));
+            report.error = Some(problem.to_string());
+        }
+        None => report.signed_head = Some(head.seq),
+    }
+}
+
+// walks the whole file checking sequence numbers, links and hashes, and that
+// the entry the head points at has the signed hash. A missing file is an
+// empty chain
+fn verify_entries(path: &Path, head: Option<&SignedHead>) -> ChainReport {
+    let mut report = ChainReport {
+        entries: 0,
+        is_valid: true,
+        last_hash: GENESIS_HASH.to_string(),
+        broken_at: None,
+        error: None,
+        signed_head: None,
+    };
+    let lines = match read_entries(path) {
+        Ok(lines) => lines,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return report,
+        Err(err) => {
+            report.is_valid = false;
+            report.error = Some(format!("Error reading audit log: {err}"));
+            return report;
+        }
+    };
+    for (line_number, line) in lines.enumerate() {
+        let entry = line.map_err(|err| err.to_string()).and_then(|line| {
+            serde_json::from_str::<AuditEntry>(&line).map_err(|err| err.to_string())
+        });
+        let problem = match &entry {
+            Err(err) => Some(format!("line {}: {err}", line_number + 1)),
+            Ok(entry) if entry.seq != report.entries => Some(format!(
+                "expected seq {}, found {}",
+                report.entries, entry.seq
+            )),
+            Ok(entry) if entry.prev_hash != report.last_hash => {
+                Some("prev_hash does not match the previous entry".to_string())
+            }
+            Ok(entry) if entry.hash != entry.compute_hash() => {
+                Some("hash does not match the entry".to_string())
+            }
+            Ok(entry)
+                if head.is_some_and(|head| head.seq == entry.seq && head.hash != entry.hash) =>
+            {
+                Some("hash does not match the signed head".to_string())
+            }
+            Ok(_) => None,
+        };
+        if let Some(problem) = problem {
+            report.is_valid = false;
+            report.broken_at = Some(match &entry {
+                Ok(entry) => entry.seq,
+                Err(_) => line_number as u64 + 1,
+            });
+            report.error = Some(problem);
+            return report;
+        }
+        if let Ok(entry) = entry {
+            report.entries += 1;
+            report.last_hash = entry.hash;
+        }
+    }
+    report
+}
+
+// entries with from <= seq < to
+pub fn export(path: &Path, from: u64, to: u64) -> std::io::Result<Vec<AuditEntry>> {
+    let mut entries = Vec::new();
+    for line in read_entries(path)? {
+        let entry: AuditEntry = serde_json::from_str(&line?)?;
+        if entry.seq >= to {
+            break;
+        }
+        if entry.seq >= from {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+fn print_report(report: &ChainReport) -> std::io::Result<()> {
+    println!("{}", serde_json::to_string_pretty(report)?);
+    if report.is_valid {
+        Ok(())
+    } else {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "audit log chain is broken",
+        ))
+    }
+}
+
+// `--audit-verify [file [public key]]` prints the chain report and fails when
+// it is broken, the key defaults to the one in ATTESTATION_KEY_FILE, which is
+// only read. `--audit-export <from> <to> [file]` prints the entries as JSON
+// lines
+pub fn run_cli(config: &Config) -> Option<std::io::Result<()>> {
+    let default_path = config.audit_log_file.as_path();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some(AUDIT_VERIFY_ARG) => {
+            let path = args
+                .get(1)
+                .map(PathBuf::from)
+                .unwrap_or_else(|| default_path.to_path_buf());
+            let public_key = match args.get(2) {
+                Some(public_key) => Ok(public_key.clone()),
+                None => attestation::read_public_key(&config.attestation_key_file).map_err(|err| {
+                    std::io::Error::new(
+                        err.kind(),
+                        format!(
+                            "Error reading attestation key {}: {err}, pass the public key \
+                                 as --audit-verify <file> <public key>",
+                            config.attestation_key_file.display()
+                        ),
+                    )
+                }),
+            };
+            Some(public_key.and_then(|public_key| print_report(&verify_chain(&path, &public_key))))
+        }
+        Some(AUDIT_EXPORT_ARG) => {
+            let range = match (args.get(1), args.get(2)) {
+                (Some(from), Some(to)) => from.parse::<u64>().ok().zip(to.parse::<u64>().ok()),
+                _ => None,
+            };
+            let (from, to) = match range {
+                Some(range) => range,
+                None => {
+                    return Some(Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "usage: --audit-export <from> <to> [file]",
+                    )))
+                }
+            };
+            let path = args
+                .get(3)
+                .map(PathBuf::from)
+                .unwrap_or_else(|| default_path.to_path_buf());
+            Some(export(&path, from, to).and_then(|entries| {
+                let mut stdout = std::io::stdout();
+                for entry in entries {
+                    writeln!(stdout, "{}", serde_json::to_string(&entry)?)?;
+                }
+                stdout.flush()
+            }))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    const KEY: [u8; 32] = [7u8; 32];
+
+    fn public_key_of(key: &SigningKey) -> String {
+        hex::encode(key.verifying_key().to_bytes())
+    }
+
+    fn entry(seq: u64, prev_hash: &str) -> AuditEntry {
+        let mut entry = AuditEntry {
+            seq,
+            tx_id: format!("tx-{seq}"),
+            verify_type: 1,
+            backend: "risc0".to_string(),
+            inputs: vec![InputHash {
+                path: format!("/spool/proof-{seq}"),
+                sha256: Some(hex::encode([seq as u8; 32])),
+            }],
+            is_valid: seq % 2 == 0,
+            result: VerificationOutcome::from_valid(seq % 2 == 0),
+            error: None,
+            started_at: 1_700_000_000 + seq,
+            finished_at: 1_700_000_001 + seq,
+            prev_hash: prev_hash.to_string(),
+            hash: String::new(),
+        };
+        entry.hash = entry.compute_hash();
+        entry
+    }
+
+    fn chain(count: u64) -> Vec<AuditEntry> {
+        let mut entries: Vec<AuditEntry> = Vec::new();
+        for seq in 0..count {
+            let prev_hash = entries
+                .last()
+                .map_or_else(|| GENESIS_HASH.to_string(), |entry| entry.hash.clone());
+            entries.push(entry(seq, &prev_hash));
+        }
+        entries
+    }
+
+    fn write_entries(path: &Path, entries: &[AuditEntry]) {
+        let lines: String = entries
+            .iter()
+            .map(|entry| serde_json::to_string(entry).unwrap() + "\n")
+            .collect();
+        fs::write(path, lines).unwrap();
+    }
+
+    fn write_signed_head(path: &Path, entry: &AuditEntry, key: &SigningKey) -> SignedHead {
+        let mut head = SignedHead {
+            seq: entry.seq,
+            hash: entry.hash.clone(),
+            signed_at: entry.finished_at,
+            public_key: public_key_of(key),
+            signature: String::new(),
+        };
+        head.signature = hex::encode(key.sign(&head_message(&head)).to_bytes());
+        fs::write(head_file(path), serde_json::to_vec(&head).unwrap()).unwrap();
+        head
+    }
+
+    #[test]
+    fn accepts_an_intact_chain() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+        let key = SigningKey::from_bytes(&KEY);
+        let entries = chain(3);
+        write_entries(&path, &entries);
+        write_signed_head(&path, &entries[2], &key);
+
+        let report = verify_chain(&path, &public_key_of(&key));
+        assert!(report.is_valid, "{:?}", report.error);
+        assert_eq!(report.entries, 3);
+        assert_eq!(report.last_hash, entries[2].hash);
+        assert_eq!(report.signed_head, Some(2));
+
+        // entries appended after the last signature are still checked
+        write_signed_head(&path, &entries[1], &key);
+        let report = verify_chain(&path, &public_key_of(&key));
+        assert!(report.is_valid, "{:?}", report.error);
+        assert_eq!(report.signed_head, Some(1));
+    }
+
+    #[test]
+    fn treats_a_missing_log_as_an_empty_chain() {
+        let dir = tempfile::tempdir().unwrap();
+        let report = verify_chain(
+            &dir.path().join("audit.log"),
+            &public_key_of(&SigningKey::from_bytes(&KEY)),
+        );
+        assert!(report.is_valid);
+        assert_eq!(report.entries, 0);
+        assert_eq!(report.last_hash, GENESIS_HASH);
+    }
+
+    #[test]
+    fn rejects_entries_without_a_signed_head() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+        write_entries(&path, &chain(2));
+        let report = verify_chain(&path, &public_key_of(&SigningKey::from_bytes(&KEY)));
+        assert!(!report.is_valid);
+        assert_eq!(
+            report.error.as_deref(),
+            Some("the chain head is not signed")
+        );
+    }
+
+    #[test]
+    fn rejects_tampered_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+        let key = SigningKey::from_bytes(&KEY);
+        let entries = chain(3);
+        write_signed_head(&path, &entries[2], &key);
+
+        // an edited field without its hash
+        let mut tampered = entries.clone();
+        tampered[1].is_valid = !tampered[1].is_valid;
+        write_entries(&path, &tampered);
+        let report = verify_chain(&path, &public_key_of(&key));
+        assert!(!report.is_valid);
+        assert_eq!(report.broken_at, Some(1));
+        assert_eq!(
+            report.error.as_deref(),
+            Some("hash does not match the entry")
+        );
+
+        // a removed entry
+        write_entries(&path, &[entries[0].clone(), entries[2].clone()]);
+        let report = verify_chain(&path, &public_key_of(&key));
+        assert!(!report.is_valid);
+        assert_eq!(report.broken_at, Some(2));
+
+        // the whole chain rehashed from the edit on, which the signed head
+        // still catches
+        let mut rehashed = entries[..1].to_vec();
+        for seq in 1..3 {
+            let mut entry = entry(seq, &rehashed.last().unwrap().hash);
+            entry.tx_id = format!("forged-{seq}");
+            entry.hash = entry.compute_hash();
+            rehashed.push(entry);
+        }
+        write_entries(&path, &rehashed);
+        let report = verify_chain(&path, &public_key_of(&key));
+        assert!(!report.is_valid);
+        assert_eq!(report.broken_at, Some(2));
+        assert_eq!(
+            report.error.as_deref(),
+            Some("hash does not match the signed head")
+        );
+
+        // entries cut off below the signed head
+        write_entries(&path, &entries[..2]);
+        let report = verify_chain(&path, &public_key_of(&key));
+        assert!(!report.is_valid);
+        assert_eq!(
+            report.error.as_deref(),
+            Some("the chain ends before its signed head")
+        );
+    }
+
+    #[test]
+    fn rejects_a_bad_head_signature() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+        let key = SigningKey::from_bytes(&KEY);
+        let other_key = SigningKey::from_bytes(&[8u8; 32]);
+        let entries = chain(2);
+        write_entries(&path, &entries);
+
+        // signed by another key
+        write_signed_head(&path, &entries[1], &other_key);
+        let report = verify_chain(&path, &public_key_of(&key));
+        assert!(!report.is_valid);
+        assert_eq!(
+            report.error.as_deref(),
+            Some("the chain head is signed by another key")
+        );
+
+        // signed by another key but claiming ours
+        let mut head = write_signed_head(&path, &entries[1], &other_key);
+        head.public_key = public_key_of(&key);
+        fs::write(head_file(&path), serde_json::to_vec(&head).unwrap()).unwrap();
+        let report = verify_chain(&path, &public_key_of(&key));
+        assert!(!report.is_valid);
+        assert_eq!(
+            report.error.as_deref(),
+            Some("the chain head signature is not valid")
+        );
+
+        // a signed field changed after signing
+        let mut head = write_signed_head(&path, &entries[1], &key);
+        head.signed_at += 1;
+        fs::write(head_file(&path), serde_json::to_vec(&head).unwrap()).unwrap();
+        let report = verify_chain(&path, &public_key_of(&key));
+        assert!(!report.is_valid);
+        assert_eq!(
+            report.error.as_deref(),
+            Some("the chain head signature is not valid")
+        );
+
+        // a head that isn't JSON
+        fs::write(head_file(&path), b"{\"seq\":").unwrap();
+        let report = verify_chain(&path, &public_key_of(&key));
+        assert!(!report.is_valid);
+        assert!(report
+            .error
+            .unwrap()
+            .starts_with("Error reading signed head"));
+    }
+
+    #[test]
+    fn truncates_a_torn_final_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+        let key = SigningKey::from_bytes(&KEY);
+        let entries = chain(3);
+        write_entries(&path, &entries);
+        write_signed_head(&path, &entries[2], &key);
+        let complete = fs::read(&path).unwrap();
+
+        let torn = serde_json::to_string(&entry(3, &entries[2].hash)).unwrap();
+        let mut log = complete.clone();
+        log.extend_from_slice(&torn.as_bytes()[..torn.len() / 2]);
+        fs::write(&path, log).unwrap();
+        let report = verify_chain(&path, &public_key_of(&key));
+        assert!(!report.is_valid);
+        assert_eq!(report.broken_at, Some(4));
+
+        truncate_torn_tail(&path).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), complete);
+        let report = verify_chain(&path, &public_key_of(&key));
+        assert!(report.is_valid, "{:?}", report.error);
+        assert_eq!(report.entries, 3);
+
+        // a complete log is left alone
+        truncate_torn_tail(&path).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), complete);
+
+        // a log torn in its first line is emptied
+        fs::write(&path, &torn.as_bytes()[..10]).unwrap();
+        truncate_torn_tail(&path).unwrap();
+        assert!(fs::read(&path).unwrap().is_empty());
+
+        // and a missing one is fine
+        truncate_torn_tail(&dir.path().join("missing.log")).unwrap();
+    }
+}
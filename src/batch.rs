@@ -0,0 +1,540 @@
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+use crate::attestation;
+use crate::config::Config;
+use crate::models::{Attestation, BatchCommitment, BatchInclusion, MerkleStep, VerificationStatus};
+use crate::retention::unix_timestamp;
+
+pub const BATCH_DOMAIN: &str = "jugalbandi-batch-v1";
+
+const PENDING_FILE: &str = "pending.jsonl";
+
+// the hash of the leaves and internal nodes of a batch tree. With EVM
+// attestations on the tree is built from keccak256 so a contract can check
+// inclusion against the signed root
+#[derive(Debug, Clone, Copy)]
+enum TreeHash {
+    Sha256,
+    Keccak256,
+}
+
+impl TreeHash {
+    fn for_config(config: &Config) -> Self {
+        if config.evm_attestation {
+            TreeHash::Keccak256
+        } else {
+            TreeHash::Sha256
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            TreeHash::Sha256 => "sha256",
+            TreeHash::Keccak256 => "keccak256",
+        }
+    }
+
+    // sha256(0x00 || attestation message), or keccak256 of the ABI encoded
+    // attestation a contract rebuilds from its fields
+    fn leaf(self, attestation: &Attestation) -> [u8; 32] {
+        match self {
+            TreeHash::Sha256 => Sha256::new()
+                .chain_update([0u8])
+                .chain_update(attestation::message(attestation))
+                .finalize()
+                .into(),
+            TreeHash::Keccak256 => attestation::keccak256(&attestation::evm_encode(attestation)),
+        }
+    }
+
+    // sha256(0x01 || left || right), or keccak256(abi.encodePacked(left, right)),
+    // a keccak leaf hashes more than 64 bytes so it can't be passed off as a node
+    fn node(self, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        match self {
+            TreeHash::Sha256 => Sha256::new()
+                .chain_update([1u8])
+                .chain_update(left)
+                .chain_update(right)
+                .finalize()
+                .into(),
+            TreeHash::Keccak256 => {
+                attestation::keccak256(&[left.as_slice(), right.as_slice()].concat())
+            }
+        }
+    }
+}
+
+// pairs are hashed level by level and an odd node out is carried up
+// unchanged, so every leaf's proof lists only real siblings
+fn merkle_tree(leaves: &[[u8; 32]], tree_hash: TreeHash) -> ([u8; 32], Vec<Vec<MerkleStep>>) {
+    let mut proofs = vec![Vec::new(); leaves.len()];
+    let mut positions: Vec<usize> = (0..leaves.len()).collect();
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        for (proof, position) in proofs.iter_mut().zip(positions.iter_mut()) {
+            let sibling = *position ^ 1;
+            if let Some(hash) = level.get(sibling) {
+                proof.push(MerkleStep {
+                    hash: hex::encode(hash),
+                    position: if sibling < *position { "left" } else { "right" }.to_string(),
+                });
+            }
+            *position /= 2;
+        }
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => tree_hash.node(left, right),
+                [single] => *single,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    (level.first().copied().unwrap_or_default(), proofs)
+}
+
+// the signed message is the compact JSON array
+// [domain, batch_id, hash, root, leaves, sealed_at]
+fn root_message(batch: &BatchCommitment) -> Vec<u8> {
+    serde_json::to_vec(&(
+        BATCH_DOMAIN,
+        batch.batch_id,
+        &batch.hash,
+        &batch.root,
+        batch.leaves,
+        batch.sealed_at,
+    ))
+    .unwrap_or_default()
+}
+
+// abi.encode(bytes32 domain, uint64 batchId, bytes32 root, uint64 leaves,
+//     uint64 sealedAt)
+fn root_evm_encode(batch_id: u64, root: &[u8; 32], leaves: usize, sealed_at: u64) -> Vec<u8> {
+    [
+        attestation::keccak256(BATCH_DOMAIN.as_bytes()),
+        attestation::uint_word(batch_id),
+        *root,
+        attestation::uint_word(leaves as u64),
+        attestation::uint_word(sealed_at),
+    ]
+    .concat()
+}
+
+// what is written to <batch_id>.json once a batch is sealed
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SealedBatch {
+    pub commitment: BatchCommitment,
+    pub inclusions: Vec<BatchInclusion>,
+}
+
+fn seal(batch_id: u64, pending: &[Attestation]) -> SealedBatch {
+    let config = Config::init();
+    let tree_hash = TreeHash::for_config(config);
+    let leaves: Vec<[u8; 32]> = pending
+        .iter()
+        .map(|attestation| tree_hash.leaf(attestation))
+        .collect();
+    let (root, proofs) = merkle_tree(&leaves, tree_hash);
+    let sealed_at = unix_timestamp();
+    let mut commitment = BatchCommitment {
+        batch_id,
+        hash: tree_hash.name().to_string(),
+        root: hex::encode(root),
+        leaves: leaves.len(),
+        sealed_at,
+        public_key: attestation::public_key(),
+        signature: String::new(),
+        evm_attestation: None,
+    };
+    commitment.signature = attestation::sign(&root_message(&commitment));
+    if config.evm_attestation {
+        match attestation::sign_evm(&root_evm_encode(batch_id, &root, leaves.len(), sealed_at)) {
+            Ok(evm_attestation) => commitment.evm_attestation = Some(evm_attestation),
+            Err(err) => warn!("Error signing EVM batch root: {:?}", err),
+        }
+    }
+    let inclusions = proofs
+        .into_iter()
+        .zip(pending)
+        .enumerate()
+        .map(|(leaf_index, (proof, attestation))| BatchInclusion {
+            tx_id: attestation.tx_id.clone(),
+            batch_id,
+            root: commitment.root.clone(),
+            leaf_index,
+            leaf: hex::encode(leaves[leaf_index]),
+            proof,
+            root_signature: commitment.signature.clone(),
+        })
+        .collect();
+    SealedBatch {
+        commitment,
+        inclusions,
+    }
+}
+
+fn batch_file(dir: &Path, batch_id: u64) -> PathBuf {
+    dir.join(format!("{batch_id}.json"))
+}
+
+// a missing batch is None, like a missing directory
+pub fn load_batch(dir: &Path, batch_id: u64) -> std::io::Result<Option<SealedBatch>> {
+    match fs::read(batch_file(dir, batch_id)) {
+        Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+// written to a temporary file first so a crash never leaves half a batch
+fn write_batch(dir: &Path, sealed: &SealedBatch) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let path = batch_file(dir, sealed.commitment.batch_id);
+    let tmp_path = path.with_extension("json.tmp");
+    let mut file = fs::File::create(&tmp_path)?;
+    file.write_all(&serde_json::to_vec(sealed)?)?;
+    file.sync_data()?;
+    fs::rename(tmp_path, path)
+}
+
+fn last_batch_id(dir: &Path) -> u64 {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return 0,
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .filter_map(|path| path.file_stem()?.to_str()?.parse::<u64>().ok())
+        .max()
+        .unwrap_or(0)
+}
+
+// unparsable lines, such as one torn by a crash, are dropped with a warning
+fn read_pending(dir: &Path) -> Vec<Attestation> {
+    let file = match fs::File::open(dir.join(PENDING_FILE)) {
+        Ok(file) => file,
+        Err(_) => return Vec::new(),
+    };
+    BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(&line) {
+            Ok(attestation) => Some(attestation),
+            Err(err) => {
+                warn!("Dropping unparsable pending batch entry: {:?}", err);
+                None
+            }
+        })
+        .collect()
+}
+
+// sealed batches are written to BATCH_DIR as <batch_id>.json and attestations
+// waiting for the next batch are appended to pending.jsonl, so batch ids keep
+// counting up and queued results survive a restart
+pub struct BatchStore {
+    dir: PathBuf,
+    next_batch_id: u64,
+    pending: Vec<Attestation>,
+}
+
+impl BatchStore {
+    pub fn open(dir: &Path) -> Self {
+        let last_batch_id = last_batch_id(dir);
+        let mut pending = read_pending(dir);
+        // a crash between writing a batch and clearing the pending file
+        // leaves its results in both
+        if let Ok(Some(last_batch)) = load_batch(dir, last_batch_id) {
+            let sealed: HashSet<&String> = last_batch
+                .inclusions
+                .iter()
+                .map(|inclusion| &inclusion.tx_id)
+                .collect();
+            pending.retain(|attestation| !sealed.contains(&attestation.tx_id));
+        }
+        info!(
+            "Batch store {} continues at batch {} with {} pending results",
+            dir.display(),
+            last_batch_id + 1,
+            pending.len()
+        );
+        BatchStore {
+            dir: dir.to_path_buf(),
+            next_batch_id: last_batch_id + 1,
+            pending,
+        }
+    }
+
+    // the attestation is batched even when it can't be persisted
+    pub fn push(&mut self, attestation: &Attestation) -> std::io::Result<()> {
+        self.pending.push(attestation.clone());
+        fs::create_dir_all(&self.dir)?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.dir.join(PENDING_FILE))?;
+        let mut line = serde_json::to_vec(attestation)?;
+        line.push(b'\n');
+        file.write_all(&line)?;
+        file.sync_data()
+    }
+
+    // None when nothing is pending. A batch that can't be written is left
+    // pending and retried with the same id
+    pub fn seal(&mut self) -> std::io::Result<Option<SealedBatch>> {
+        if self.pending.is_empty() {
+            return Ok(None);
+        }
+        let sealed = seal(self.next_batch_id, &self.pending);
+        write_batch(&self.dir, &sealed)?;
+        self.next_batch_id += 1;
+        self.pending.clear();
+        if let Err(err) = fs::write(self.dir.join(PENDING_FILE), b"") {
+            warn!("Error clearing pending batch entries: {:?}", err);
+        }
+        Ok(Some(sealed))
+    }
+}
+
+pub async fn run_batcher(
+    batch_store: Arc<Mutex<BatchStore>>,
+    statuses: Arc<Mutex<HashMap<String, VerificationStatus>>>,
+) {
+    loop {
+        let config = Config::init();
+        tokio::time::sleep(Duration::from_secs(config.batch_interval_secs)).await;
+        let sealed = match batch_store.lock().await.seal() {
+            Ok(Some(sealed)) => sealed,
+            Ok(None) => continue,
+            Err(err) => {
+                warn!("Error sealing batch: {:?}", err);
+                continue;
+            }
+        };
+        info!(
+            "Sealed batch {} with {} results, root {}",
+            sealed.commitment.batch_id, sealed.commitment.leaves, sealed.commitment.root
+        );
+        let mut statuses = statuses.lock().await;
+        for inclusion in sealed.inclusions {
+            match statuses.get_mut(&inclusion.tx_id) {
+                Some(status) => status.batch = Some(inclusion),
+                None => warn!("No status for batched result {}", inclusion.tx_id),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::VerificationOutcome;
+
+    fn leaves(count: u8) -> Vec<[u8; 32]> {
+        (1..=count).map(|byte| [byte; 32]).collect()
+    }
+
+    fn root_hex(count: u8, tree_hash: TreeHash) -> String {
+        hex::encode(merkle_tree(&leaves(count), tree_hash).0)
+    }
+
+    // walks a leaf up its proof the way a client checking inclusion does
+    fn fold(leaf: [u8; 32], proof: &[MerkleStep], tree_hash: TreeHash) -> [u8; 32] {
+        proof.iter().fold(leaf, |hash, step| {
+            let sibling: [u8; 32] = hex::decode(&step.hash).unwrap().try_into().unwrap();
+            match step.position.as_str() {
+                "left" => tree_hash.node(&sibling, &hash),
+                "right" => tree_hash.node(&hash, &sibling),
+                position => panic!("unknown position {position}"),
+            }
+        })
+    }
+
+    fn attestation(tx_id: &str) -> Attestation {
+        Attestation {
+            tx_id: tx_id.to_string(),
+            backend: "groth16".to_string(),
+            program_id: None,
+            proof_hash: Some("00".repeat(32)),
+            public_outputs_hash: "11".repeat(32),
+            is_valid: true,
+            result: VerificationOutcome::Valid,
+            timestamp: 1_700_000_000,
+            public_key: "22".repeat(32),
+            signature: None,
+        }
+    }
+
+    fn pending_tx_ids(batch_store: &BatchStore) -> Vec<&str> {
+        batch_store
+            .pending
+            .iter()
+            .map(|attestation| attestation.tx_id.as_str())
+            .collect()
+    }
+
+    // reference roots over the leaves [0x01; 32], [0x02; 32], .. computed
+    // independently from the node rules above
+    #[test]
+    fn matches_reference_sha256_roots() {
+        assert_eq!(root_hex(1, TreeHash::Sha256), "01".repeat(32));
+        assert_eq!(
+            root_hex(2, TreeHash::Sha256),
+            "b331da6ec49d4547d9942a6727e5123f69bed5a0b97ac171cfbfd6201431fcfa"
+        );
+        assert_eq!(
+            root_hex(3, TreeHash::Sha256),
+            "30a02c023aa9f351be86615ceedfd51bc34d9a2a003174b4132ea26e5c2d0417"
+        );
+        assert_eq!(
+            root_hex(5, TreeHash::Sha256),
+            "6ff0c6808a81ea1b1baaf8ea3bba84644330f485b1b48f097bfde8b76fc02e7c"
+        );
+    }
+
+    #[test]
+    fn matches_reference_keccak_roots() {
+        assert_eq!(
+            hex::encode(attestation::keccak256(b"abc")),
+            "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+        );
+        assert_eq!(root_hex(1, TreeHash::Keccak256), "01".repeat(32));
+        assert_eq!(
+            root_hex(2, TreeHash::Keccak256),
+            "346d8c96a2454213fcc0daff3c96ad0398148181b9fa6488f7ae2c0af5b20aa0"
+        );
+        assert_eq!(
+            root_hex(3, TreeHash::Keccak256),
+            "f8f23a80fd4d99d9d231122e1f115145412be3856b23abcc338903e32a80c4ef"
+        );
+        assert_eq!(
+            root_hex(5, TreeHash::Keccak256),
+            "644baaabdf96074e4c3774fcd82cc717cd4d53c48da8ac1e3029ebe434c64996"
+        );
+    }
+
+    #[test]
+    fn proves_every_leaf() {
+        for tree_hash in [TreeHash::Sha256, TreeHash::Keccak256] {
+            for count in 1..=9 {
+                let leaves = leaves(count);
+                let (root, proofs) = merkle_tree(&leaves, tree_hash);
+                assert_eq!(proofs.len(), leaves.len());
+                for (leaf, proof) in leaves.iter().zip(&proofs) {
+                    assert_eq!(fold(*leaf, proof, tree_hash), root, "{count} leaves");
+                }
+            }
+        }
+    }
+
+    // an odd node out is carried up, so its proof skips the levels where it
+    // had no sibling
+    #[test]
+    fn carries_odd_leaves_up() {
+        let leaves = leaves(5);
+        let (root, proofs) = merkle_tree(&leaves, TreeHash::Sha256);
+        assert_eq!(proofs[4].len(), 1);
+        assert_eq!(proofs[4][0].position, "left");
+        assert_eq!(proofs[0].len(), 3);
+        let left = merkle_tree(&leaves[..4], TreeHash::Sha256).0;
+        assert_eq!(TreeHash::Sha256.node(&left, &leaves[4]), root);
+    }
+
+    #[test]
+    fn separates_leaf_and_tree_hashes() {
+        let leaves = leaves(2);
+        let sha256 = merkle_tree(&leaves, TreeHash::Sha256).0;
+        let keccak = merkle_tree(&leaves, TreeHash::Keccak256).0;
+        assert_ne!(sha256, keccak);
+        assert_eq!(merkle_tree(&[], TreeHash::Sha256).0, [0u8; 32]);
+    }
+
+    #[test]
+    fn reloads_pending_results() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut batch_store = BatchStore::open(dir.path());
+        assert_eq!(batch_store.next_batch_id, 1);
+        assert!(batch_store.pending.is_empty());
+        for tx_id in ["a", "b", "c"] {
+            batch_store.push(&attestation(tx_id)).unwrap();
+        }
+
+        let batch_store = BatchStore::open(dir.path());
+        assert_eq!(batch_store.next_batch_id, 1);
+        assert_eq!(pending_tx_ids(&batch_store), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn drops_torn_pending_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut batch_store = BatchStore::open(dir.path());
+        batch_store.push(&attestation("a")).unwrap();
+        let mut pending = OpenOptions::new()
+            .append(true)
+            .open(dir.path().join(PENDING_FILE))
+            .unwrap();
+        pending.write_all(b"{\"tx_id\": \"b\", \"back").unwrap();
+
+        let batch_store = BatchStore::open(dir.path());
+        assert_eq!(pending_tx_ids(&batch_store), vec!["a"]);
+    }
+
+    // a crash after writing batch 3 but before clearing pending.jsonl leaves
+    // its results in both, they are not batched twice
+    #[test]
+    fn continues_after_the_last_batch() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut batch_store = BatchStore::open(dir.path());
+        for tx_id in ["a", "b", "c"] {
+            batch_store.push(&attestation(tx_id)).unwrap();
+        }
+        let sealed = SealedBatch {
+            commitment: BatchCommitment {
+                batch_id: 3,
+                hash: TreeHash::Sha256.name().to_string(),
+                root: "00".repeat(32),
+                leaves: 2,
+                sealed_at: 1_700_000_000,
+                public_key: String::new(),
+                signature: String::new(),
+                evm_attestation: None,
+            },
+            inclusions: ["a", "b"]
+                .iter()
+                .enumerate()
+                .map(|(leaf_index, tx_id)| BatchInclusion {
+                    tx_id: tx_id.to_string(),
+                    batch_id: 3,
+                    root: "00".repeat(32),
+                    leaf_index,
+                    leaf: "00".repeat(32),
+                    proof: Vec::new(),
+                    root_signature: String::new(),
+                })
+                .collect(),
+        };
+        write_batch(dir.path(), &sealed).unwrap();
+        fs::write(dir.path().join("1.json.tmp"), b"{").unwrap();
+
+        let batch_store = BatchStore::open(dir.path());
+        assert_eq!(batch_store.next_batch_id, 4);
+        assert_eq!(pending_tx_ids(&batch_store), vec!["c"]);
+        let loaded = load_batch(dir.path(), 3).unwrap().unwrap();
+        assert_eq!(loaded.inclusions.len(), 2);
+        assert!(load_batch(dir.path(), 2).unwrap().is_none());
+    }
+}
@@ -1,26 +1,101 @@
+use lazy_static::lazy_static;
 use log::{info, warn};
 use std::collections::{HashMap, VecDeque};
 use std::env;
+use std::fs;
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+use crate::attestation;
 use crate::errors::VerificationError;
 use crate::models::{
-    JoltProof, MidenProof, PostVerificationResult, Risc0Proof, Sp1Proof, VerificationResult,
-    VerifyProof,
+    PostVerificationResult, ShadowStats, VerificationJob, VerificationOutcome, VerificationRecord,
+    VerificationResult, VerificationStatus, VerifyProof,
 };
-use crate::services::{jolt_verifier, miden_verifier, risc0_verifier, sp1_verifier};
+use crate::quorum;
+use crate::retention::{prune_statuses, release_files, unix_timestamp};
+use crate::sandbox;
+use crate::services;
+use crate::services::envelope::Envelope;
+use crate::shadow;
+use crate::storage::{ProofStores, ResultStores};
 
 pub struct Config {
     pub port: u16,
     pub workers: usize,
     pub delete_files: bool,
     pub u_port: u16,
+    pub risc0_registered_only: bool,
+    pub risc0_receipt_kinds: Vec<String>,
+    pub risc0_hash_suites: Vec<String>,
+    pub risc0_allowed_control_ids: Vec<String>,
+    pub miden_min_security_level: u32,
+    pub miden_program_security_levels: HashMap<String, u32>,
+    pub envelope: Option<Envelope>,
+    pub spool_roots: Vec<PathBuf>,
+    pub keep_invalid: bool,
+    pub quarantine_dir: PathBuf,
+    pub halo2_key_dir: PathBuf,
+    pub retention_max_age_secs: u64,
+    pub retention_max_spool_bytes: u64,
+    pub retention_sweep_interval_secs: u64,
+    pub sandbox: bool,
+    pub sandbox_cpu_secs: u64,
+    pub sandbox_memory_bytes: u64,
+    pub sandbox_timeout_secs: u64,
+    pub backend_versions: HashMap<String, String>,
+    pub shadow_versions: HashMap<String, String>,
+    pub status_ttl_secs: u64,
+    pub status_max_entries: usize,
+    pub attestation_key_file: PathBuf,
+    pub evm_attestation: bool,
+    pub evm_attestation_key_file: PathBuf,
+    pub batch_interval_secs: u64,
+    pub audit_log_file: PathBuf,
+    pub batch_dir: PathBuf,
+    pub quorum_peers: Vec<(String, String)>,
+    pub quorum_threshold: usize,
+    pub quorum_timeout_secs: u64,
+    pub quorum_coordinators: Vec<String>,
+    pub quorum_max_request_bytes: usize,
+}
+
+fn list_var(name: &str, default: &str) -> Vec<String> {
+    env::var(name)
+        .unwrap_or_else(|_| default.to_string())
+        .split(',')
+        .map(|entry| entry.trim().to_string())
+        .filter(|entry| !entry.is_empty())
+        .collect()
+}
+
+// comma separated <backend>=<version> pairs, e.g. risc0=1.0
+fn version_map_var(name: &str) -> HashMap<String, String> {
+    list_var(name, "")
+        .into_iter()
+        .map(|entry| {
+            let (backend, version) = entry
+                .split_once('=')
+                .unwrap_or_else(|| panic!("{name} entries must be <backend>=<version>"));
+            (backend.trim().to_string(), version.trim().to_string())
+        })
+        .collect()
+}
+
+lazy_static! {
+    // read from the environment the first time it is asked for, main does so
+    // before anything else so a bad variable stops the server at startup
+    static ref CONFIG: Config = Config::from_env();
 }
 
 impl Config {
-    pub fn init() -> Self {
+    pub fn init() -> &'static Config {
+        &CONFIG
+    }
+
+    fn from_env() -> Self {
         let port = env::var("PORT")
             .unwrap_or_else(|_| "8080".to_string())
             .parse()
@@ -37,13 +112,225 @@ impl Config {
             .unwrap_or_else(|_| "0".to_string())
             .parse()
             .expect("UPort must be a number");
+        let risc0_registered_only = env::var("RISC0_REGISTERED_ONLY")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse()
+            .expect("RISC0_REGISTERED_ONLY must be a boolean");
+        // comma separated receipt kinds accepted, out of composite, succinct and groth16
+        let risc0_receipt_kinds = list_var("RISC0_RECEIPT_KINDS", "composite,succinct,groth16");
+        // comma separated hash suites segment receipts may use, empty allows all of them
+        let risc0_hash_suites = list_var("RISC0_HASH_SUITES", "");
+        // comma separated hex control IDs succinct receipts may be proven
+        // against, empty trusts the recursion circuits built into risc0-zkvm
+        let risc0_allowed_control_ids = list_var("RISC0_ALLOWED_CONTROL_IDS", "")
+            .into_iter()
+            .map(|control_id| control_id.to_lowercase())
+            .collect();
+        let miden_min_security_level = env::var("MIDEN_MIN_SECURITY_LEVEL")
+            .unwrap_or_else(|_| "96".to_string())
+            .parse()
+            .expect("MIDEN_MIN_SECURITY_LEVEL must be a number");
+        // comma separated list of <program_hash>=<bits>
+        let miden_program_security_levels = env::var("MIDEN_PROGRAM_SECURITY_LEVELS")
+            .unwrap_or_default()
+            .split(',')
+            .filter(|entry| !entry.trim().is_empty())
+            .map(|entry| {
+                let (program_hash, level) = entry
+                    .split_once('=')
+                    .expect("MIDEN_PROGRAM_SECURITY_LEVELS entries must be <program_hash>=<bits>");
+                let level = level
+                    .trim()
+                    .parse()
+                    .expect("MIDEN_PROGRAM_SECURITY_LEVELS levels must be numbers");
+                (program_hash.trim().to_string(), level)
+            })
+            .collect();
+        // unset keeps each backend on the framing it has always been fed
+        let envelope = env::var("ENVELOPE")
+            .ok()
+            .map(|envelope| envelope.parse().expect("ENVELOPE must be raw or hypersdk"));
+        // comma separated list of directories proof and ELF files may live in
+        let spool_roots = env::var("SPOOL_ROOTS")
+            .unwrap_or_default()
+            .split(',')
+            .filter(|root| !root.trim().is_empty())
+            .map(|root| PathBuf::from(root.trim()))
+            .collect();
+        let keep_invalid = env::var("KEEP_INVALID")
+            .unwrap_or_else(|_| "true".to_string())
+            .parse()
+            .expect("KEEP_INVALID must be a boolean");
+        let quarantine_dir =
+            PathBuf::from(env::var("QUARANTINE_DIR").unwrap_or_else(|_| "quarantine".to_string()));
+        // registered halo2 params and verifying keys, keep it outside the spool roots
+        let halo2_key_dir =
+            PathBuf::from(env::var("HALO2_KEY_DIR").unwrap_or_else(|_| "halo2-keys".to_string()));
+        // 0 disables the respective retention rule
+        let retention_max_age_secs = env::var("RETENTION_MAX_AGE_SECS")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse()
+            .expect("RETENTION_MAX_AGE_SECS must be a number");
+        let retention_max_spool_bytes = env::var("RETENTION_MAX_SPOOL_BYTES")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse()
+            .expect("RETENTION_MAX_SPOOL_BYTES must be a number");
+        let retention_sweep_interval_secs = env::var("RETENTION_SWEEP_INTERVAL_SECS")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse()
+            .expect("RETENTION_SWEEP_INTERVAL_SECS must be a number");
+        let sandbox = env::var("SANDBOX")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse()
+            .expect("SANDBOX must be a boolean");
+        // 0 leaves the respective rlimit unset
+        let sandbox_cpu_secs = env::var("SANDBOX_CPU_SECS")
+            .unwrap_or_else(|_| "300".to_string())
+            .parse()
+            .expect("SANDBOX_CPU_SECS must be a number");
+        let sandbox_memory_bytes = env::var("SANDBOX_MEMORY_BYTES")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse()
+            .expect("SANDBOX_MEMORY_BYTES must be a number");
+        let sandbox_timeout_secs = env::var("SANDBOX_TIMEOUT_SECS")
+            .unwrap_or_else(|_| "600".to_string())
+            .parse()
+            .expect("SANDBOX_TIMEOUT_SECS must be a number");
+        let backend_versions = version_map_var("BACKEND_VERSIONS");
+        // candidate versions every proof is also verified with, the result
+        // is only compared and never returned
+        let shadow_versions = version_map_var("SHADOW_VERSIONS");
+        // statuses are kept in memory, 0 disables the respective bound
+        let status_ttl_secs = env::var("STATUS_TTL_SECS")
+            .unwrap_or_else(|_| "86400".to_string())
+            .parse()
+            .expect("STATUS_TTL_SECS must be a number");
+        let status_max_entries = env::var("STATUS_MAX_ENTRIES")
+            .unwrap_or_else(|_| "100000".to_string())
+            .parse()
+            .expect("STATUS_MAX_ENTRIES must be a number");
+        let attestation_key_file = PathBuf::from(
+            env::var("ATTESTATION_KEY_FILE").unwrap_or_else(|_| "attestation.key".to_string()),
+        );
+        // also sign results with a secp256k1 key for on-chain settlement
+        let evm_attestation = env::var("EVM_ATTESTATION")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse()
+            .expect("EVM_ATTESTATION must be a boolean");
+        let evm_attestation_key_file = PathBuf::from(
+            env::var("EVM_ATTESTATION_KEY_FILE")
+                .unwrap_or_else(|_| "attestation-evm.key".to_string()),
+        );
+        // seconds between sealed batches, 0 signs every attestation on its own
+        let batch_interval_secs = env::var("BATCH_INTERVAL_SECS")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse()
+            .expect("BATCH_INTERVAL_SECS must be a number");
+        let audit_log_file =
+            PathBuf::from(env::var("AUDIT_LOG_FILE").unwrap_or_else(|_| "audit.log".to_string()));
+        let batch_dir =
+            PathBuf::from(env::var("BATCH_DIR").unwrap_or_else(|_| "batches".to_string()));
+        // comma separated <base url>=<hex ed25519 public key> of the peers a
+        // job is fanned out to, every peer's vote has to be signed by its key
+        let quorum_peers: Vec<(String, String)> = list_var("QUORUM_PEERS", "")
+            .into_iter()
+            .map(|entry| {
+                let (peer, public_key) = entry
+                    .rsplit_once('=')
+                    .expect("QUORUM_PEERS entries must be <base url>=<public key>");
+                (peer.trim().to_string(), public_key.trim().to_lowercase())
+            })
+            .collect();
+        // votes needed out of the peers plus this instance, 0 requires all
+        let quorum_threshold = match env::var("QUORUM_THRESHOLD")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse()
+            .expect("QUORUM_THRESHOLD must be a number")
+        {
+            0 => quorum_peers.len() + 1,
+            threshold => threshold,
+        };
+        if quorum_threshold > quorum_peers.len() + 1 {
+            panic!("QUORUM_THRESHOLD is larger than the number of QUORUM_PEERS plus one");
+        }
+        let quorum_timeout_secs = env::var("QUORUM_TIMEOUT_SECS")
+            .unwrap_or_else(|_| "60".to_string())
+            .parse()
+            .expect("QUORUM_TIMEOUT_SECS must be a number");
+        // comma separated hex ed25519 public keys of the coordinators this
+        // instance votes for, /quorum/verify rejects everyone when unset
+        let quorum_coordinators = list_var("QUORUM_COORDINATORS", "")
+            .into_iter()
+            .map(|public_key| public_key.to_lowercase())
+            .collect();
+        // quorum requests carry the proof and program files
+        let quorum_max_request_bytes = env::var("QUORUM_MAX_REQUEST_BYTES")
+            .unwrap_or_else(|_| "268435456".to_string())
+            .parse()
+            .expect("QUORUM_MAX_REQUEST_BYTES must be a number");
         Config {
             port,
             workers,
             delete_files,
             u_port,
+            risc0_registered_only,
+            risc0_receipt_kinds,
+            risc0_hash_suites,
+            risc0_allowed_control_ids,
+            miden_min_security_level,
+            miden_program_security_levels,
+            envelope,
+            spool_roots,
+            keep_invalid,
+            quarantine_dir,
+            halo2_key_dir,
+            retention_max_age_secs,
+            retention_max_spool_bytes,
+            retention_sweep_interval_secs,
+            sandbox,
+            sandbox_cpu_secs,
+            sandbox_memory_bytes,
+            sandbox_timeout_secs,
+            backend_versions,
+            shadow_versions,
+            status_ttl_secs,
+            status_max_entries,
+            attestation_key_file,
+            evm_attestation,
+            evm_attestation_key_file,
+            batch_interval_secs,
+            audit_log_file,
+            batch_dir,
+            quorum_peers,
+            quorum_threshold,
+            quorum_timeout_secs,
+            quorum_coordinators,
+            quorum_max_request_bytes,
         }
     }
+
+    pub fn canonical_spool_roots(&self) -> Result<Vec<PathBuf>, String> {
+        self.spool_roots
+            .iter()
+            .map(|root| {
+                fs::canonicalize(root).map_err(|err| {
+                    format!("SPOOL_ROOTS entry {} is not usable: {err}", root.display())
+                })
+            })
+            .collect()
+    }
+
+    // miden files have always arrived as they are, and snarkjs, gnark, plonky2
+    // and halo2 write their files without an id. The other backends sit
+    // behind the hypersdk id prefix
+    pub fn envelope_for(&self, verify_type: u8) -> Envelope {
+        self.envelope.unwrap_or(match verify_type {
+            VERIFY_TYPE_MIDEN | VERIFY_TYPE_GROTH16 | VERIFY_TYPE_PLONKY2 | VERIFY_TYPE_HALO2 => {
+                Envelope::Raw
+            }
+            _ => Envelope::HyperSdk,
+        })
+    }
 }
 
 impl Clone for Config {
@@ -53,12 +340,67 @@ impl Clone for Config {
             workers: self.workers,
             delete_files: self.delete_files,
             u_port: self.u_port,
+            risc0_registered_only: self.risc0_registered_only,
+            risc0_receipt_kinds: self.risc0_receipt_kinds.clone(),
+            risc0_hash_suites: self.risc0_hash_suites.clone(),
+            risc0_allowed_control_ids: self.risc0_allowed_control_ids.clone(),
+            miden_min_security_level: self.miden_min_security_level,
+            miden_program_security_levels: self.miden_program_security_levels.clone(),
+            envelope: self.envelope,
+            spool_roots: self.spool_roots.clone(),
+            keep_invalid: self.keep_invalid,
+            quarantine_dir: self.quarantine_dir.clone(),
+            halo2_key_dir: self.halo2_key_dir.clone(),
+            retention_max_age_secs: self.retention_max_age_secs,
+            retention_max_spool_bytes: self.retention_max_spool_bytes,
+            retention_sweep_interval_secs: self.retention_sweep_interval_secs,
+            sandbox: self.sandbox,
+            sandbox_cpu_secs: self.sandbox_cpu_secs,
+            sandbox_memory_bytes: self.sandbox_memory_bytes,
+            sandbox_timeout_secs: self.sandbox_timeout_secs,
+            backend_versions: self.backend_versions.clone(),
+            shadow_versions: self.shadow_versions.clone(),
+            status_ttl_secs: self.status_ttl_secs,
+            status_max_entries: self.status_max_entries,
+            attestation_key_file: self.attestation_key_file.clone(),
+            evm_attestation: self.evm_attestation,
+            evm_attestation_key_file: self.evm_attestation_key_file.clone(),
+            batch_interval_secs: self.batch_interval_secs,
+            audit_log_file: self.audit_log_file.clone(),
+            batch_dir: self.batch_dir.clone(),
+            quorum_peers: self.quorum_peers.clone(),
+            quorum_threshold: self.quorum_threshold,
+            quorum_timeout_secs: self.quorum_timeout_secs,
+            quorum_coordinators: self.quorum_coordinators.clone(),
+            quorum_max_request_bytes: self.quorum_max_request_bytes,
         }
     }
 }
 
+// the verify_type clients send for each backend
+pub const VERIFY_TYPE_SP1: u8 = 1;
+pub const VERIFY_TYPE_MIDEN: u8 = 2;
+pub const VERIFY_TYPE_RISC0: u8 = 3;
+pub const VERIFY_TYPE_JOLT: u8 = 4;
+pub const VERIFY_TYPE_GROTH16: u8 = 5;
+pub const VERIFY_TYPE_PLONKY2: u8 = 6;
+pub const VERIFY_TYPE_HALO2: u8 = 7;
+
+pub fn backend_name(verify_type: u8) -> &'static str {
+    match verify_type {
+        VERIFY_TYPE_SP1 => "sp1",
+        VERIFY_TYPE_MIDEN => "miden",
+        VERIFY_TYPE_RISC0 => "risc0",
+        VERIFY_TYPE_JOLT => "jolt",
+        VERIFY_TYPE_GROTH16 => "groth16",
+        VERIFY_TYPE_PLONKY2 => "plonky2",
+        VERIFY_TYPE_HALO2 => "halo2",
+        _ => "unknown",
+    }
+}
+
 pub fn handle_verification_result(
-    verification_result: Result<VerificationResult, VerificationError>,
+    verification_result: &Result<VerificationResult, VerificationError>,
 ) -> bool {
     match verification_result {
         Ok(result) => {
@@ -72,76 +414,236 @@ pub fn handle_verification_result(
     }
 }
 
+// records a finished verification: collects the quorum, attests the outcome,
+// stores the status, batch entry and audit entry, releases the files and
+// sends the callback. Runs off the worker in coordinator mode so waiting for
+// peers doesn't hold up the queue
+async fn finish(
+    verification_proof: VerifyProof,
+    job: Option<VerificationJob>,
+    verification_result: Result<VerificationResult, VerificationError>,
+    started_at: u64,
+    result_stores: ResultStores,
+    in_flight: Arc<Mutex<HashMap<String, VerifyProof>>>,
+) {
+    let files = job.as_ref().map(VerificationJob::files).unwrap_or_default();
+    let submission = job
+        .as_ref()
+        .and_then(|job| serde_json::to_value(job).ok())
+        .unwrap_or_default();
+    let is_valid = handle_verification_result(&verification_result);
+    let security_level = verification_result
+        .as_ref()
+        .ok()
+        .and_then(|result| result.security_level);
+    let public_inputs = verification_result
+        .as_ref()
+        .ok()
+        .and_then(|result| result.public_inputs.clone());
+    let proof_kind = verification_result
+        .as_ref()
+        .ok()
+        .and_then(|result| result.proof_kind.clone());
+    let record = VerificationRecord {
+        tx_id: verification_proof.tx_id.clone(),
+        verify_type: verification_proof.verify_type,
+        backend: backend_name(verification_proof.verify_type).to_string(),
+        submission,
+        files,
+        started_at,
+        finished_at: unix_timestamp(),
+    };
+    let attestation = attestation::attest(
+        &record.tx_id,
+        &record.backend,
+        job.as_ref(),
+        &verification_result,
+        record.finished_at,
+    );
+    let config = Config::init();
+    // in coordinator mode the outcome is the one k of the n votes agree on,
+    // and that is what gets attested and recorded
+    let quorum = match &job {
+        Some(job) if !config.quorum_peers.is_empty() => {
+            Some(quorum::collect(&record.tx_id, job, &attestation).await)
+        }
+        _ => None,
+    };
+    let (outcome, attestation) = match &quorum {
+        Some(quorum) => (
+            quorum.outcome,
+            attestation::with_outcome(&attestation, quorum.outcome),
+        ),
+        None => (VerificationOutcome::from_valid(is_valid), attestation),
+    };
+    let evm_attestation = if config.evm_attestation && config.batch_interval_secs == 0 {
+        match attestation::attest_evm(&attestation) {
+            Ok(evm_attestation) => Some(evm_attestation),
+            Err(err) => {
+                warn!("Error signing EVM attestation: {:?}", err);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let mut statuses = result_stores.statuses.lock().await;
+    prune_statuses(&mut statuses, config);
+    statuses.insert(
+        record.tx_id.clone(),
+        VerificationStatus {
+            tx_id: record.tx_id.clone(),
+            backend: record.backend.clone(),
+            result: outcome,
+            is_valid: outcome.is_valid(),
+            security_level,
+            public_inputs: public_inputs.clone(),
+            proof_kind: proof_kind.clone(),
+            error: verification_result
+                .as_ref()
+                .err()
+                .map(|err| err.to_string()),
+            finished_at: record.finished_at,
+            attestation: attestation.clone(),
+            evm_attestation: evm_attestation.clone(),
+            batch: None,
+            quorum: quorum.clone(),
+        },
+    );
+    drop(statuses);
+    if config.batch_interval_secs > 0 {
+        if let Err(err) = result_stores.batch_store.lock().await.push(&attestation) {
+            warn!(
+                "Error persisting batch entry for {}: {:?}",
+                record.tx_id, err
+            );
+        }
+    }
+    // written before the files are released so their hashes are recorded
+    if let Err(err) =
+        result_stores
+            .audit_log
+            .lock()
+            .await
+            .append(&record, &verification_result, outcome)
+    {
+        warn!("Error writing audit entry for {}: {:?}", record.tx_id, err);
+    }
+    release_files(&record, &verification_result);
+    in_flight.lock().await.remove(&record.tx_id);
+    // Send POST request to the other server on successful verification
+    let port = config.u_port;
+    let url_str = format!("http://127.0.0.1:{}/submit-result", port.to_string());
+    info!("Sending verification proof to: {}", url_str);
+    let url = match reqwest::Url::from_str(&url_str) {
+        Ok(url) => url,
+        Err(err) => {
+            warn!("Failed to parse URL {}: {:?}", url_str, err);
+            return;
+        }
+    };
+    let client = reqwest::Client::new();
+    let map = PostVerificationResult {
+        tx_id: verification_proof.tx_id,
+        result: outcome,
+        is_valid: outcome.is_valid(),
+        security_level,
+        public_inputs,
+        proof_kind,
+        attestation,
+        evm_attestation,
+        quorum,
+    };
+    let response = match client.post(url).json(&map).send().await {
+        Ok(response) => response,
+        Err(err) => {
+            warn!("Failed to send POST request: {:?}", err);
+            return;
+        }
+    };
+    info!("Response: {:?}", response);
+    if response.status().is_success() {
+        info!("Verification proof sent successfully!");
+    } else {
+        warn!("Failed to send verification proof: {}", response.status());
+    }
+}
+
 pub async fn process_verification_queue(
     queue: Arc<Mutex<VecDeque<VerifyProof>>>,
-    _sp1_hashmap: Arc<Mutex<HashMap<String, Sp1Proof>>>,
-    _risc0_hashmap: Arc<Mutex<HashMap<String, Risc0Proof>>>,
-    _miden_hashmap: Arc<Mutex<HashMap<String, MidenProof>>>,
-    _jolt_hashmap: Arc<Mutex<HashMap<String, JoltProof>>>,
+    peer_queue: Arc<Mutex<VecDeque<quorum::PeerJob>>>,
+    in_flight: Arc<Mutex<HashMap<String, VerifyProof>>>,
+    proof_stores: ProofStores,
+    shadow_stats: Arc<Mutex<HashMap<String, ShadowStats>>>,
+    result_stores: ResultStores,
 ) {
     loop {
-        let mut queue = queue.lock().await;
-
-        if queue.is_empty() {
-            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        let sandboxed = Config::init().sandbox;
+        let peer_job = peer_queue.lock().await.pop_front();
+        if let Some(peer_job) = peer_job {
+            quorum::run_peer_job(peer_job, sandboxed).await;
             continue;
         }
 
-        let verification_proof = queue.pop_front().unwrap();
-        info!("Processing verification proof: {:?}", verification_proof);
-        let is_valid;
-        match verification_proof.verify_type {
-            1 => {
-                let sp1_hashmap = _sp1_hashmap.lock().await;
-                let sp1_proof = sp1_hashmap.get(&verification_proof.tx_id).unwrap();
-                let verification_result = sp1_verifier::verify(sp1_proof).await;
-                is_valid = handle_verification_result(verification_result);
-            }
-            2 => {
-                let miden_hashmap = _miden_hashmap.lock().await;
-                let miden_proof = miden_hashmap.get(&verification_proof.tx_id).unwrap();
-                let verification_result = miden_verifier::verify(miden_proof).await;
-                is_valid = handle_verification_result(verification_result);
-            }
-            3 => {
-                let risc0_hashmap = _risc0_hashmap.lock().await;
-                let risc0_proof = risc0_hashmap.get(&verification_proof.tx_id).unwrap();
-                let verification_result = risc0_verifier::verify(risc0_proof).await;
-                is_valid = handle_verification_result(verification_result);
+        // the job is in flight before the queue is let go, see queued_files
+        let verification_proof = {
+            let mut queue = queue.lock().await;
+            let verification_proof = queue.pop_front();
+            if let Some(verification_proof) = &verification_proof {
+                in_flight
+                    .lock()
+                    .await
+                    .insert(verification_proof.tx_id.clone(), verification_proof.clone());
             }
-            4 => {
-                let jolt_hashmap = _jolt_hashmap.lock().await;
-                let jolt_proof = jolt_hashmap.get(&verification_proof.tx_id).unwrap();
-                let verification_result = jolt_verifier::verify(jolt_proof).await;
-                is_valid = handle_verification_result(verification_result);
+            verification_proof
+        };
+        let verification_proof = match verification_proof {
+            Some(verification_proof) => verification_proof,
+            None => {
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                continue;
             }
-            _ => {
+        };
+        info!("Processing verification proof: {:?}", verification_proof);
+        let started_at = unix_timestamp();
+        let job = proof_stores
+            .job(verification_proof.verify_type, &verification_proof.tx_id)
+            .await;
+        let verification_result = match &job {
+            Some(job) if sandboxed => sandbox::verify_sandboxed(job.clone()).await,
+            Some(job) => services::verify_isolated(job.clone()).await,
+            None if !services::is_compiled(verification_proof.verify_type) => {
                 warn!("Invalid proof type");
-                is_valid = false;
+                Err(VerificationError::InvalidVerifyType(
+                    verification_proof.verify_type,
+                ))
             }
-        }
-        // Send POST request to the other server on successful verification
-        let config = Config::init();
-        let port = config.u_port;
-        let url_str = format!("http://127.0.0.1:{}/submit-result", port.to_string());
-        info!("Sending verification proof to: {}", url_str);
-        let url = reqwest::Url::from_str(&url_str).expect("Failed to parse URL");
-        let client = reqwest::Client::new();
-        let map = PostVerificationResult {
-            tx_id: verification_proof.tx_id,
-            is_valid,
+            None => Err(VerificationError::UnknownProof(
+                verification_proof.tx_id.clone(),
+            )),
         };
-        let response = client
-            .post(url)
-            .json(&map)
-            .send()
-            .await
-            .expect("Failed to send POST request");
-        info!("Response: {:?}", response);
-        if response.status().is_success() {
-            info!("Verification proof sent successfully!");
+        if let Some(job) = &job {
+            // copies the files before they are released
+            shadow::spawn_compare(
+                &verification_proof.tx_id,
+                job,
+                matches!(&verification_result, Ok(result) if result.is_valid),
+                sandboxed,
+                &shadow_stats,
+            );
+        }
+        let finished = finish(
+            verification_proof,
+            job,
+            verification_result,
+            started_at,
+            result_stores.clone(),
+            in_flight.clone(),
+        );
+        if Config::init().quorum_peers.is_empty() {
+            finished.await;
         } else {
-            warn!("Failed to send verification proof: {}", response.status());
+            tokio::spawn(finished);
         }
     }
 }
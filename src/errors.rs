@@ -16,6 +16,49 @@ pub enum VerificationError {
     DigestError(String),
     #[error("Error parsing inputs stack JSON: {0}")]
     ParseError(String),
+    #[error("Error loading ELF: {0}")]
+    ElfError(String),
+    #[error("Error loading program: {0}")]
+    ProgramError(String),
+    #[error("Invalid file envelope: {0}")]
+    EnvelopeError(String),
+    #[error("Rejected file path: {0}")]
+    PathError(String),
+    #[error("Invalid verify type: {0}")]
+    InvalidVerifyType(u8),
+    #[error("No proof registered: {0}")]
+    UnknownProof(String),
+    #[error("Error reading proof: {0}")]
+    ProofError(String),
+    #[error("Error reading verifying key: {0}")]
+    KeyError(String),
+    #[error("Unsupported proof version: {0}")]
+    VersionMismatch(String),
+    #[error("Verifier panicked: {0}")]
+    Panic(String),
+    #[error("Sandboxed verification failed: {0}")]
+    Sandbox(String),
+    #[error("Quorum verification failed: {0}")]
+    Quorum(String),
+}
+
+impl VerificationError {
+    // the error followed by the context it was raised with
+    pub fn chain(&self) -> Vec<String> {
+        let mut chain = vec![self.to_string()];
+        match self {
+            VerificationError::IOError(_, context)
+            | VerificationError::JSONError(_, context)
+            | VerificationError::BincodeError(_, context)
+            | VerificationError::JsonErrIOErr(_, _, context) => {
+                if !context.is_empty() {
+                    chain.push(context.clone());
+                }
+            }
+            _ => {}
+        }
+        chain
+    }
 }
 
 impl From<std::io::Error> for VerificationError {
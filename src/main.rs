@@ -1,63 +1,167 @@
+use std::io;
 use std::vec;
 
 use actix_web::middleware::Logger;
 use actix_web::{web, App, HttpServer};
+use log::{error, info, warn};
 use tokio::task;
 
+use crate::batch::run_batcher;
 use crate::config::{process_verification_queue, Config};
 use crate::logging::init_logger;
 use crate::models::Ports;
+use crate::retention::run_sweeper;
 use crate::routes::{
-    hello, ping, ping_single, verify, verify_jolt, verify_miden, verify_risc0, verify_sp1,
+    attestation_public_key, audit_entries, audit_head, audit_verify, backends, batch_commitment,
+    configure_backends, hello, ping, ping_single, quarantine_file, quarantine_manifest,
+    quorum_verify, retention_report, shadow_stats, status, verify,
+};
+use crate::storage::{
+    ProofStores, ResultStores, AUDIT_LOG, BATCH_STORE, IN_FLIGHT, LAST_SWEEP_REPORT, PEER_QUEUE,
+    SHADOW_STATS, SPOOL_ROOTS, VERIFICATION_STATUSES, VERIFY_QUEUE,
 };
-use crate::storage::{JOLT_HASHMAP, MIDEN_HASHMAP, RISC0_HASHMAP, SP1_HASHMAP, VERIFY_QUEUE};
 
+mod attestation;
+mod audit;
+mod batch;
 mod config;
 mod errors;
 mod logging;
 mod models;
+mod quarantine;
+mod quorum;
+mod retention;
 mod routes;
+mod sandbox;
 mod services;
+mod shadow;
 mod storage;
 
+#[cfg(not(any(
+    feature = "sp1",
+    feature = "risc0",
+    feature = "miden",
+    feature = "jolt",
+    feature = "groth16",
+    feature = "plonky2",
+    feature = "halo2"
+)))]
+compile_error!("at least one proof backend feature must be enabled");
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
+    if sandbox::is_sandbox_child() {
+        init_logger();
+        return sandbox::run_child().await;
+    }
     let config = Config::init();
+    if let Some(result) = audit::run_cli(config) {
+        return result;
+    }
     init_logger();
+    let spool_roots = match SPOOL_ROOTS.as_ref() {
+        Ok(spool_roots) => spool_roots,
+        Err(err) => {
+            error!("{err}");
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, err.clone()));
+        }
+    };
+    shadow::check_config(config);
+    if spool_roots.is_empty() {
+        warn!("SPOOL_ROOTS is not set, proof and ELF files are accepted from any path");
+    }
     let queue = VERIFY_QUEUE.clone();
-    let sp1_hashmap = SP1_HASHMAP.clone();
-    let risc0_hashmap = RISC0_HASHMAP.clone();
-    let miden_hashmap = MIDEN_HASHMAP.clone();
-    let jolt_hashmap = JOLT_HASHMAP.clone();
+    let peer_queue = PEER_QUEUE.clone();
+    let in_flight = IN_FLIGHT.clone();
+    let proof_stores = ProofStores::shared();
+    let last_sweep_report = LAST_SWEEP_REPORT.clone();
+    let shadow_stats_map = SHADOW_STATS.clone();
+    let statuses = VERIFICATION_STATUSES.clone();
+    let batch_store = BATCH_STORE.clone();
+    let audit_log = match AUDIT_LOG.as_ref() {
+        Ok(audit_log) => audit_log.clone(),
+        Err(err) => {
+            error!("{err}");
+            return Err(io::Error::new(io::ErrorKind::InvalidData, err.clone()));
+        }
+    };
+    info!(
+        "Signing attestations with ed25519 key {}",
+        attestation::public_key()
+    );
+    if config.evm_attestation {
+        info!("Signing EVM attestations as {}", attestation::evm_address());
+    }
     let port_index = storage::PORT_INDEX.clone();
     task::spawn(process_verification_queue(
         queue.clone(),
-        sp1_hashmap.clone(),
-        risc0_hashmap.clone(),
-        miden_hashmap.clone(),
-        jolt_hashmap.clone(),
+        peer_queue.clone(),
+        in_flight.clone(),
+        proof_stores.clone(),
+        shadow_stats_map.clone(),
+        ResultStores {
+            statuses: statuses.clone(),
+            batch_store: batch_store.clone(),
+            audit_log: audit_log.clone(),
+        },
     ));
+    if !config.quorum_peers.is_empty() {
+        info!(
+            "Coordinating a {}-of-{} quorum",
+            config.quorum_threshold,
+            config.quorum_peers.len() + 1
+        );
+    }
+    if config.quorum_coordinators.is_empty() {
+        info!("QUORUM_COORDINATORS is not set, quorum requests are rejected");
+    }
+    if config.batch_interval_secs > 0 {
+        task::spawn(run_batcher(batch_store.clone(), statuses.clone()));
+    }
+    if config.retention_sweep_interval_secs > 0 {
+        task::spawn(run_sweeper(
+            queue.clone(),
+            in_flight.clone(),
+            proof_stores.clone(),
+            last_sweep_report.clone(),
+        ));
+    }
+    let quorum_max_request_bytes = config.quorum_max_request_bytes;
     HttpServer::new(move || {
         App::new()
             .wrap(Logger::default())
             .app_data(web::Data::new(queue.clone()))
-            .app_data(web::Data::new(sp1_hashmap.clone()))
-            .app_data(web::Data::new(risc0_hashmap.clone()))
-            .app_data(web::Data::new(miden_hashmap.clone()))
-            .app_data(web::Data::new(jolt_hashmap.clone()))
+            .app_data(web::Data::new(proof_stores.clone()))
+            .app_data(web::Data::new(last_sweep_report.clone()))
+            .app_data(web::Data::new(shadow_stats_map.clone()))
+            .app_data(web::Data::new(statuses.clone()))
+            .app_data(web::Data::new(peer_queue.clone()))
             .app_data(web::Data::new(Ports {
                 instantiated_ports: vec![8081, 8082, 8083, 8084, 8085],
                 uninstantiated_ports: vec![8086, 8087, 8088, 8089, 8090],
             }))
             .app_data(web::Data::new(port_index.clone()))
+            .configure(configure_backends)
             .service(hello)
-            .service(verify_sp1)
-            .service(verify_miden)
-            .service(verify_risc0)
-            .service(verify_jolt)
+            .service(backends)
             .service(verify)
             .service(ping)
             .service(ping_single)
+            .service(retention_report)
+            .service(shadow_stats)
+            .service(status)
+            .service(batch_commitment)
+            .service(audit_verify)
+            .service(audit_head)
+            .service(audit_entries)
+            .service(
+                web::resource("/quorum/verify")
+                    .app_data(web::JsonConfig::default().limit(quorum_max_request_bytes))
+                    .route(web::post().to(quorum_verify)),
+            )
+            .service(attestation_public_key)
+            .service(quarantine_manifest)
+            .service(quarantine_file)
     })
     .workers(config.workers)
     .bind(("127.0.0.1", config.port))?
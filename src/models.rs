@@ -1,62 +1,488 @@
 use serde::{Deserialize, Serialize};
 
+use crate::config::{self, backend_name};
+use crate::services;
+use crate::services::envelope::Envelope;
+
+#[cfg(feature = "sp1")]
 #[derive(Deserialize, Debug)]
 pub struct ProofDataSP1 {
     pub tx_id: String,
     pub proof_file_path: String,
     pub elf_file_path: String,
+    // "2.0", "2.x" or "sp1@2.0", defaults to the configured version
+    pub version: Option<String>,
+    pub envelope: Option<Envelope>,
 }
 
-#[derive(Deserialize, Debug)]
+#[cfg(feature = "sp1")]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Sp1Proof {
     pub proof_file_path: String,
     pub elf_file_path: String,
+    pub version: String,
+    pub envelope: Option<Envelope>,
 }
 
+#[cfg(feature = "sp1")]
+impl Sp1Proof {
+    pub fn files(&self) -> Vec<String> {
+        vec![self.proof_file_path.clone(), self.elf_file_path.clone()]
+    }
+}
+
+#[cfg(feature = "jolt")]
 #[derive(Deserialize, Debug)]
 pub struct ProofDataJolt {
     pub tx_id: String,
     pub proof_file_path: String,
     pub elf_file_path: String,
+    pub envelope: Option<Envelope>,
 }
 
-#[derive(Deserialize, Debug)]
+#[cfg(feature = "jolt")]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct JoltProof {
     pub proof_file_path: String,
     pub elf_file_path: String,
+    pub envelope: Option<Envelope>,
 }
 
+#[cfg(feature = "jolt")]
+impl JoltProof {
+    pub fn files(&self) -> Vec<String> {
+        vec![self.proof_file_path.clone(), self.elf_file_path.clone()]
+    }
+}
+
+#[cfg(feature = "miden")]
 #[derive(Deserialize, Debug)]
 pub struct ProofDataMiden {
     pub tx_id: String,
-    pub program_hash: String,
+    pub program_hash: Option<String>,
+    pub program_name: Option<String>,
     pub inputs_stack: String,
     pub outputs_stack: String,
     pub proof_file_path: String,
+    pub kernel_procedures: Option<Vec<String>>,
+    pub envelope: Option<Envelope>,
 }
 
-#[derive(Deserialize, Debug)]
+#[cfg(feature = "miden")]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct MidenProof {
     pub program_hash: String,
     pub inputs_stack: String,
     pub outputs_stack: String,
     pub proof_file_path: String,
+    pub kernel_procedures: Option<Vec<String>>,
+    pub envelope: Option<Envelope>,
 }
 
+#[cfg(feature = "miden")]
+impl MidenProof {
+    pub fn files(&self) -> Vec<String> {
+        vec![self.proof_file_path.clone()]
+    }
+}
+
+#[cfg(feature = "miden")]
+#[derive(Deserialize, Debug)]
+pub struct MidenProgram {
+    pub program_name: String,
+    pub program_file_path: String,
+    // "masm" source or a serialized "program", defaults to "masm"
+    pub format: Option<String>,
+    pub envelope: Option<Envelope>,
+}
+
+#[cfg(feature = "miden")]
+#[derive(Debug, Clone)]
+pub struct MidenProgramInfo {
+    pub program_hash: String,
+    pub kernel_procedures: Vec<String>,
+}
+
+#[cfg(feature = "risc0")]
 #[derive(Deserialize, Debug)]
 pub struct ProodDataRisc0 {
     pub tx_id: String,
     pub proof_file_path: String,
-    pub risc_zero_image_id: String,
+    pub risc_zero_image_id: Option<String>,
+    pub program_id: Option<String>,
+    // "1.0", "1.x" or "risc0@1.0", defaults to the registered program's version
+    pub version: Option<String>,
+    pub envelope: Option<Envelope>,
 }
 
+#[cfg(feature = "risc0")]
 #[derive(Deserialize, Debug)]
+pub struct Risc0Program {
+    pub program_id: String,
+    pub elf_file_path: String,
+    pub version: Option<String>,
+    pub envelope: Option<Envelope>,
+}
+
+#[cfg(feature = "risc0")]
+#[derive(Debug, Clone)]
+pub struct Risc0ProgramInfo {
+    pub image_id: [u32; 8],
+    pub version: String,
+}
+
+#[cfg(feature = "risc0")]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Risc0Proof {
     pub proof_file_path: String,
     pub risc_zero_image_id: String,
+    pub version: String,
+    pub envelope: Option<Envelope>,
+}
+
+#[cfg(feature = "risc0")]
+impl Risc0Proof {
+    pub fn files(&self) -> Vec<String> {
+        vec![self.proof_file_path.clone()]
+    }
+}
+
+#[cfg(feature = "groth16")]
+#[derive(Deserialize, Debug)]
+pub struct Groth16Key {
+    pub key_id: String,
+    pub vk_file_path: String,
+    // "snarkjs" or "gnark", defaults to "snarkjs"
+    pub format: Option<String>,
+    pub envelope: Option<Envelope>,
+}
+
+#[cfg(feature = "groth16")]
+#[derive(Debug, Clone)]
+pub struct Groth16KeyInfo {
+    // hex of the arkworks compressed verifying key
+    pub vk: String,
+    pub vk_hash: String,
+}
+
+#[cfg(feature = "groth16")]
+#[derive(Deserialize, Debug)]
+pub struct ProofDataGroth16 {
+    pub tx_id: String,
+    // a key registered through /groth16-register
+    pub key_id: String,
+    pub proof_file_path: String,
+    // decimal or 0x prefixed hex field elements, in circuit order
+    pub public_inputs: Vec<String>,
+    // "snarkjs" or "gnark", defaults to "snarkjs"
+    pub format: Option<String>,
+    pub envelope: Option<Envelope>,
+}
+
+#[cfg(feature = "groth16")]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Groth16Proof {
+    pub vk: String,
+    pub vk_hash: String,
+    pub proof_file_path: String,
+    pub public_inputs: Vec<String>,
+    pub format: Option<String>,
+    pub envelope: Option<Envelope>,
 }
 
+#[cfg(feature = "groth16")]
+impl Groth16Proof {
+    pub fn files(&self) -> Vec<String> {
+        vec![self.proof_file_path.clone()]
+    }
+}
+
+#[cfg(feature = "plonky2")]
+#[derive(Deserialize, Debug)]
+pub struct Plonky2Circuit {
+    pub circuit_id: String,
+    pub common_data_file_path: String,
+    pub verifier_data_file_path: String,
+    pub envelope: Option<Envelope>,
+}
+
+#[cfg(feature = "plonky2")]
+#[derive(Debug, Clone)]
+pub struct Plonky2CircuitInfo {
+    // hex of the serialized common circuit data and verifier only data
+    pub common_data: String,
+    pub verifier_data: String,
+    pub circuit_digest: String,
+}
+
+#[cfg(feature = "plonky2")]
+#[derive(Deserialize, Debug)]
+pub struct ProofDataPlonky2 {
+    pub tx_id: String,
+    // a circuit registered through /plonky2-register
+    pub circuit_id: String,
+    pub proof_file_path: String,
+    pub envelope: Option<Envelope>,
+}
+
+#[cfg(feature = "plonky2")]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Plonky2Proof {
+    pub common_data: String,
+    pub verifier_data: String,
+    pub circuit_digest: String,
+    pub proof_file_path: String,
+    pub envelope: Option<Envelope>,
+}
+
+#[cfg(feature = "plonky2")]
+impl Plonky2Proof {
+    pub fn files(&self) -> Vec<String> {
+        vec![self.proof_file_path.clone()]
+    }
+}
+
+#[cfg(feature = "halo2")]
+#[derive(Deserialize, Debug)]
+pub struct Halo2Keys {
+    pub key_id: String,
+    // name of a circuit compiled into the server
+    pub circuit: String,
+    pub params_file_path: String,
+    pub vk_file_path: String,
+    pub envelope: Option<Envelope>,
+}
+
+#[cfg(feature = "halo2")]
+#[derive(Debug, Clone)]
+pub struct Halo2KeysInfo {
+    pub circuit: String,
+    // copies kept in HALO2_KEY_DIR
+    pub params_file_path: String,
+    pub vk_file_path: String,
+    pub vk_hash: String,
+}
+
+#[cfg(feature = "halo2")]
 #[derive(Deserialize, Debug)]
+pub struct ProofDataHalo2 {
+    pub tx_id: String,
+    // keys registered through /halo2-register
+    pub key_id: String,
+    pub proof_file_path: String,
+    // one list of 0x prefixed hex values per instance column
+    pub instances: Vec<Vec<String>>,
+    pub envelope: Option<Envelope>,
+}
+
+#[cfg(feature = "halo2")]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Halo2Proof {
+    pub circuit: String,
+    pub params_file_path: String,
+    pub vk_file_path: String,
+    pub vk_hash: String,
+    pub proof_file_path: String,
+    pub instances: Vec<Vec<String>>,
+    pub envelope: Option<Envelope>,
+}
+
+#[cfg(feature = "halo2")]
+impl Halo2Proof {
+    pub fn files(&self) -> Vec<String> {
+        vec![self.proof_file_path.clone()]
+    }
+}
+
+// a registered proof taken off the queue, owned so it can be moved into the
+// task that verifies it
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum VerificationJob {
+    #[cfg(feature = "sp1")]
+    Sp1(Sp1Proof),
+    #[cfg(feature = "miden")]
+    Miden(MidenProof),
+    #[cfg(feature = "risc0")]
+    Risc0(Risc0Proof),
+    #[cfg(feature = "jolt")]
+    Jolt(JoltProof),
+    #[cfg(feature = "groth16")]
+    Groth16(Groth16Proof),
+    #[cfg(feature = "plonky2")]
+    Plonky2(Plonky2Proof),
+    #[cfg(feature = "halo2")]
+    Halo2(Halo2Proof),
+}
+
+impl VerificationJob {
+    pub fn files(&self) -> Vec<String> {
+        match self {
+            #[cfg(feature = "sp1")]
+            VerificationJob::Sp1(proof) => proof.files(),
+            #[cfg(feature = "miden")]
+            VerificationJob::Miden(proof) => proof.files(),
+            #[cfg(feature = "risc0")]
+            VerificationJob::Risc0(proof) => proof.files(),
+            #[cfg(feature = "jolt")]
+            VerificationJob::Jolt(proof) => proof.files(),
+            #[cfg(feature = "groth16")]
+            VerificationJob::Groth16(proof) => proof.files(),
+            #[cfg(feature = "plonky2")]
+            VerificationJob::Plonky2(proof) => proof.files(),
+            #[cfg(feature = "halo2")]
+            VerificationJob::Halo2(proof) => proof.files(),
+        }
+    }
+
+    pub fn verify_type(&self) -> u8 {
+        match self {
+            #[cfg(feature = "sp1")]
+            VerificationJob::Sp1(_) => config::VERIFY_TYPE_SP1,
+            #[cfg(feature = "miden")]
+            VerificationJob::Miden(_) => config::VERIFY_TYPE_MIDEN,
+            #[cfg(feature = "risc0")]
+            VerificationJob::Risc0(_) => config::VERIFY_TYPE_RISC0,
+            #[cfg(feature = "jolt")]
+            VerificationJob::Jolt(_) => config::VERIFY_TYPE_JOLT,
+            #[cfg(feature = "groth16")]
+            VerificationJob::Groth16(_) => config::VERIFY_TYPE_GROTH16,
+            #[cfg(feature = "plonky2")]
+            VerificationJob::Plonky2(_) => config::VERIFY_TYPE_PLONKY2,
+            #[cfg(feature = "halo2")]
+            VerificationJob::Halo2(_) => config::VERIFY_TYPE_HALO2,
+        }
+    }
+
+    pub fn proof_file(&self) -> &str {
+        match self {
+            #[cfg(feature = "sp1")]
+            VerificationJob::Sp1(proof) => &proof.proof_file_path,
+            #[cfg(feature = "miden")]
+            VerificationJob::Miden(proof) => &proof.proof_file_path,
+            #[cfg(feature = "risc0")]
+            VerificationJob::Risc0(proof) => &proof.proof_file_path,
+            #[cfg(feature = "jolt")]
+            VerificationJob::Jolt(proof) => &proof.proof_file_path,
+            #[cfg(feature = "groth16")]
+            VerificationJob::Groth16(proof) => &proof.proof_file_path,
+            #[cfg(feature = "plonky2")]
+            VerificationJob::Plonky2(proof) => &proof.proof_file_path,
+            #[cfg(feature = "halo2")]
+            VerificationJob::Halo2(proof) => &proof.proof_file_path,
+        }
+    }
+
+    // the identifier the backend already knows the program by
+    pub fn program_id(&self) -> Option<String> {
+        match self {
+            #[cfg(feature = "miden")]
+            VerificationJob::Miden(proof) => Some(proof.program_hash.clone()),
+            #[cfg(feature = "risc0")]
+            VerificationJob::Risc0(proof) => Some(proof.risc_zero_image_id.clone()),
+            #[cfg(feature = "groth16")]
+            VerificationJob::Groth16(proof) => Some(proof.vk_hash.clone()),
+            #[cfg(feature = "plonky2")]
+            VerificationJob::Plonky2(proof) => Some(proof.circuit_digest.clone()),
+            #[cfg(feature = "halo2")]
+            VerificationJob::Halo2(proof) => Some(proof.vk_hash.clone()),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+
+    // the file that pins the program for backends without a program id
+    pub fn program_file(&self) -> Option<&str> {
+        match self {
+            #[cfg(feature = "sp1")]
+            VerificationJob::Sp1(proof) => Some(&proof.elf_file_path),
+            #[cfg(feature = "jolt")]
+            VerificationJob::Jolt(proof) => Some(&proof.elf_file_path),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+
+    // the public outputs claimed by the submission
+    pub fn public_outputs(&self) -> Vec<String> {
+        match self {
+            #[cfg(feature = "miden")]
+            VerificationJob::Miden(proof) => vec![proof.outputs_stack.clone()],
+            #[cfg(feature = "groth16")]
+            VerificationJob::Groth16(proof) => proof.public_inputs.clone(),
+            #[cfg(feature = "halo2")]
+            VerificationJob::Halo2(proof) => proof.instances.concat(),
+            #[allow(unreachable_patterns)]
+            _ => Vec::new(),
+        }
+    }
+
+    // the backend version the job is verified with, None for backends that
+    // only ship one version
+    pub fn version(&self) -> Option<&str> {
+        match self {
+            #[cfg(feature = "sp1")]
+            VerificationJob::Sp1(proof) => Some(&proof.version),
+            #[cfg(feature = "risc0")]
+            VerificationJob::Risc0(proof) => Some(&proof.version),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+
+    // every file the verifier reads, the submitted ones plus registered keys
+    pub fn input_files(&self) -> Vec<String> {
+        match self {
+            #[cfg(feature = "halo2")]
+            VerificationJob::Halo2(proof) => [
+                proof.files(),
+                vec![proof.params_file_path.clone(), proof.vk_file_path.clone()],
+            ]
+            .concat(),
+            #[allow(unreachable_patterns)]
+            _ => self.files(),
+        }
+    }
+
+    // the job with its file paths pointed at copies, given as
+    // (original, copy) pairs
+    pub fn relocated(&self, copies: &[(String, String)]) -> Option<VerificationJob> {
+        let mut value = serde_json::to_value(self).ok()?;
+        let fields = value
+            .as_object_mut()?
+            .values_mut()
+            .next()?
+            .as_object_mut()?;
+        for field in fields.values_mut() {
+            if let Some((_, copy)) = copies
+                .iter()
+                .find(|(original, _)| field.as_str() == Some(original))
+            {
+                *field = serde_json::Value::String(copy.clone());
+            }
+        }
+        serde_json::from_value(value).ok()
+    }
+
+    // the same job verified with another version of its backend
+    pub fn with_version(&self, version: &str) -> Option<VerificationJob> {
+        match self {
+            #[cfg(feature = "sp1")]
+            VerificationJob::Sp1(proof) => Some(VerificationJob::Sp1(Sp1Proof {
+                version: version.to_string(),
+                ..proof.clone()
+            })),
+            #[cfg(feature = "risc0")]
+            VerificationJob::Risc0(proof) => Some(VerificationJob::Risc0(Risc0Proof {
+                version: version.to_string(),
+                ..proof.clone()
+            })),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
 pub struct VerifyProof {
     pub tx_id: String,
     pub verify_type: u8,
@@ -67,15 +493,128 @@ pub struct SubmitionResult {
     pub is_submitted: bool,
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize)]
+pub struct RegistrationResult {
+    pub is_registered: bool,
+    pub program_hash: Option<String>,
+}
 
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct VerificationResult {
     pub is_valid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub security_level: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_inputs: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proof_kind: Option<String>,
 }
 
+#[cfg(any(feature = "miden", feature = "risc0"))]
 #[derive(Deserialize, Debug)]
 pub struct Proof {
-    pub proof: Vec<u8>,
+    pub proof: ProofBytes,
+}
+
+#[cfg(any(feature = "miden", feature = "risc0"))]
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum ProofBytes {
+    Array(Vec<u8>),
+    Base64(String),
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Backend {
+    pub name: String,
+    pub verify_type: u8,
+    pub versions: Vec<String>,
+    pub default_version: Option<String>,
+}
+
+impl Backend {
+    pub fn new(verify_type: u8) -> Self {
+        Backend {
+            name: backend_name(verify_type).to_string(),
+            verify_type,
+            versions: services::versions(verify_type)
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            default_version: services::resolve_version(verify_type, None)
+                .ok()
+                .map(str::to_string),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct Backends {
+    pub backends: Vec<Backend>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RemovedFile {
+    pub path: String,
+    pub bytes: u64,
+    pub reason: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SweepReport {
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub removed: Vec<RemovedFile>,
+    pub freed_bytes: u64,
+    pub remaining_files: usize,
+    pub remaining_bytes: u64,
+}
+
+// how often a candidate backend version agreed with the active one
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct ShadowStats {
+    pub active_version: String,
+    pub candidate_version: String,
+    pub compared: u64,
+    pub agreed: u64,
+    pub disagreed: u64,
+    pub candidate_errors: u64,
+    pub last_disagreement: Option<String>,
+}
+
+// what the worker knows about a finished verification
+#[derive(Debug)]
+pub struct VerificationRecord {
+    pub tx_id: String,
+    pub verify_type: u8,
+    pub backend: String,
+    pub submission: serde_json::Value,
+    pub files: Vec<String>,
+    pub started_at: u64,
+    pub finished_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct QuarantinedFile {
+    pub name: String,
+    pub original_path: String,
+    pub sha256: String,
+    pub bytes: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct QuarantineManifest {
+    pub tx_id: String,
+    pub backend: String,
+    pub verify_type: u8,
+    pub is_valid: bool,
+    pub error: Option<String>,
+    pub error_chain: Vec<String>,
+    pub submission: serde_json::Value,
+    pub files: Vec<QuarantinedFile>,
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub quarantined_at: u64,
 }
 
 #[derive(Serialize)]
@@ -85,11 +624,214 @@ pub struct Ping {
     pub uinit_port: String,
 }
 
+// is_valid is left out when the quorum settled on neither, result tells why
 #[derive(Serialize, Debug)]
 pub struct PostVerificationResult {
     pub tx_id: String,
+    pub result: VerificationOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_valid: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub security_level: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_inputs: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proof_kind: Option<String>,
+    pub attestation: Attestation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub evm_attestation: Option<EvmAttestation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quorum: Option<QuorumResult>,
+}
+
+// an ed25519 signature by this verifier over the outcome of a verification,
+// hashes are hex encoded sha256 of the files as submitted. Unsigned when the
+// result is committed to through a batch root instead
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Attestation {
+    pub tx_id: String,
+    pub backend: String,
+    pub program_id: Option<String>,
+    pub proof_hash: Option<String>,
+    pub public_outputs_hash: String,
     pub is_valid: bool,
+    pub result: VerificationOutcome,
+    pub timestamp: u64,
+    pub public_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+// a secp256k1 signature a contract can check with
+// ecrecover(digest, v, r, s) == signer
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EvmAttestation {
+    pub signer: String,
+    pub message_hash: String,
+    pub digest: String,
+    pub signature: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MerkleStep {
+    pub hash: String,
+    // "left" or "right" of the running hash
+    pub position: String,
+}
+
+// a signed Merkle root over the attestations finished during one interval,
+// hash is "sha256" or "keccak256" for the leaves and nodes of the tree
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BatchCommitment {
+    pub batch_id: u64,
+    pub hash: String,
+    pub root: String,
+    pub leaves: usize,
+    pub sealed_at: u64,
+    pub public_key: String,
+    pub signature: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub evm_attestation: Option<EvmAttestation>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BatchInclusion {
+    pub tx_id: String,
+    pub batch_id: u64,
+    pub root: String,
+    pub leaf_index: usize,
+    pub leaf: String,
+    pub proof: Vec<MerkleStep>,
+    pub root_signature: String,
+}
+
+// an input of a job fanned out to a peer, shipped with the request since
+// peers don't share the coordinator's spool. The content is base64
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QuorumFile {
+    pub path: String,
+    pub sha256: String,
+    pub content: String,
+}
+
+// a job fanned out by a quorum coordinator to one of its peers, signed with
+// the coordinator's attestation key
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QuorumRequest {
+    pub tx_id: String,
+    pub job: VerificationJob,
+    pub files: Vec<QuorumFile>,
+    pub issued_at: u64,
+    pub public_key: String,
+    pub signature: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct QuorumResponse {
+    pub attestation: Attestation,
+}
+
+// what a verification settled on, a single instance only reaches valid or
+// invalid
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationOutcome {
+    Valid,
+    Invalid,
+    // votes were cast both ways and neither side reached k
+    Disagreement,
+    // the votes cast agree but fewer than k were cast, unreachable peers and
+    // rejected votes don't count
+    NoQuorum,
+}
+
+impl VerificationOutcome {
+    pub fn from_valid(is_valid: bool) -> Self {
+        if is_valid {
+            VerificationOutcome::Valid
+        } else {
+            VerificationOutcome::Invalid
+        }
+    }
+
+    // None when the votes settled on neither
+    pub fn is_valid(self) -> Option<bool> {
+        match self {
+            VerificationOutcome::Valid => Some(true),
+            VerificationOutcome::Invalid => Some(false),
+            VerificationOutcome::Disagreement | VerificationOutcome::NoQuorum => None,
+        }
+    }
+
+    // the uint8 in the EVM encoding, invalid and valid match isValid
+    pub fn code(self) -> u8 {
+        match self {
+            VerificationOutcome::Invalid => 0,
+            VerificationOutcome::Valid => 1,
+            VerificationOutcome::Disagreement => 2,
+            VerificationOutcome::NoQuorum => 3,
+        }
+    }
 }
+
+#[derive(Serialize, Debug, Clone)]
+pub struct QuorumVote {
+    pub peer: String,
+    pub is_valid: Option<bool>,
+    pub attestation: Option<Attestation>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct QuorumResult {
+    pub outcome: VerificationOutcome,
+    pub threshold: usize,
+    pub votes: Vec<QuorumVote>,
+}
+
+// seq range of exported audit entries, to is exclusive
+#[derive(Deserialize, Debug)]
+pub struct AuditRange {
+    #[serde(default)]
+    pub from: u64,
+    pub to: Option<u64>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct AttestationKey {
+    pub algorithm: String,
+    pub public_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub evm_address: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct VerificationStatus {
+    pub tx_id: String,
+    pub backend: String,
+    pub result: VerificationOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_valid: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub security_level: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_inputs: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proof_kind: Option<String>,
+    // why the proof could not be verified, unset for valid and invalid proofs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub finished_at: u64,
+    pub attestation: Attestation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub evm_attestation: Option<EvmAttestation>,
+    // filled in once the batch holding the attestation is sealed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch: Option<BatchInclusion>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quorum: Option<QuorumResult>,
+}
+
 pub struct Ports {
     pub instantiated_ports: Vec<u16>,
     pub uninstantiated_ports: Vec<u16>,
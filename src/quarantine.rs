@@ -0,0 +1,186 @@
+use log::{info, warn};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::errors::VerificationError;
+use crate::models::{QuarantineManifest, QuarantinedFile, VerificationRecord, VerificationResult};
+use crate::retention::{move_file, unix_timestamp};
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+// longest readable part of a bundle name, well under the 255 byte limit
+const BUNDLE_PREFIX_LEN: usize = 64;
+
+// tx ids come from clients, keep them from escaping the quarantine directory.
+// Sanitizing can map distinct ids to the same name, so the name ends with a
+// short hash of the raw id
+pub fn bundle_dir(quarantine_dir: &Path, tx_id: &str) -> PathBuf {
+    let prefix: String = tx_id
+        .chars()
+        .take(BUNDLE_PREFIX_LEN)
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let digest = Sha256::digest(tx_id.as_bytes());
+    quarantine_dir.join(format!("{}-{}", prefix, hex::encode(&digest[..8])))
+}
+
+pub fn sha256_file(file_path: &Path) -> std::io::Result<(String, u64)> {
+    let bytes = fs::read(file_path)?;
+    Ok((hex::encode(Sha256::digest(&bytes)), bytes.len() as u64))
+}
+
+// moves (or copies when the originals must stay in place) the files of a
+// failed verification next to a manifest describing the submission
+pub fn quarantine(
+    quarantine_dir: &Path,
+    record: &VerificationRecord,
+    verification_result: &Result<VerificationResult, VerificationError>,
+    move_files: bool,
+) -> std::io::Result<PathBuf> {
+    let bundle = bundle_dir(quarantine_dir, &record.tx_id);
+    fs::create_dir_all(&bundle)?;
+
+    let mut files = Vec::new();
+    for (index, file) in record.files.iter().enumerate() {
+        let source = Path::new(file);
+        let file_name = source
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "file".to_string());
+        let name = format!("{}-{}", index, file_name);
+        let target = bundle.join(&name);
+        let (sha256, bytes) = match sha256_file(source) {
+            Ok(hashed) => hashed,
+            Err(err) => {
+                warn!("Error hashing {}: {:?}", file, err);
+                continue;
+            }
+        };
+        let copied = if move_files {
+            move_file(source, &target)
+        } else {
+            fs::copy(source, &target).map(|_| ())
+        };
+        if let Err(err) = copied {
+            warn!("Error quarantining {}: {:?}", file, err);
+            continue;
+        }
+        files.push(QuarantinedFile {
+            name,
+            original_path: file.clone(),
+            sha256,
+            bytes,
+        });
+    }
+
+    let (is_valid, error, error_chain) = match verification_result {
+        Ok(result) => (result.is_valid, None, vec![]),
+        Err(err) => (false, Some(err.to_string()), err.chain()),
+    };
+    let manifest = QuarantineManifest {
+        tx_id: record.tx_id.clone(),
+        backend: record.backend.clone(),
+        verify_type: record.verify_type,
+        is_valid,
+        error,
+        error_chain,
+        submission: record.submission.clone(),
+        files,
+        started_at: record.started_at,
+        finished_at: record.finished_at,
+        quarantined_at: unix_timestamp(),
+    };
+    let manifest_json = serde_json::to_vec_pretty(&manifest)?;
+    fs::write(bundle.join(MANIFEST_FILE), manifest_json)?;
+    info!(
+        "Quarantined verification {} in {}",
+        record.tx_id,
+        bundle.display()
+    );
+    Ok(bundle)
+}
+
+pub fn read_manifest(
+    quarantine_dir: &Path,
+    tx_id: &str,
+) -> Result<QuarantineManifest, VerificationError> {
+    let manifest_path = bundle_dir(quarantine_dir, tx_id).join(MANIFEST_FILE);
+    let manifest = fs::read(&manifest_path).map_err(|err| {
+        VerificationError::IOError(err, "Error reading quarantine manifest".to_string())
+    })?;
+    let manifest: QuarantineManifest = serde_json::from_slice(&manifest).map_err(|err| {
+        VerificationError::JSONError(err, "Error parsing quarantine manifest".to_string())
+    })?;
+    if manifest.tx_id != tx_id {
+        return Err(VerificationError::PathError(format!(
+            "quarantine bundle belongs to {}",
+            manifest.tx_id
+        )));
+    }
+    Ok(manifest)
+}
+
+// only files listed in the manifest can be downloaded
+pub fn read_file(
+    quarantine_dir: &Path,
+    tx_id: &str,
+    file_name: &str,
+) -> Result<Vec<u8>, VerificationError> {
+    let manifest = read_manifest(quarantine_dir, tx_id)?;
+    if file_name != MANIFEST_FILE && !manifest.files.iter().any(|file| file.name == file_name) {
+        return Err(VerificationError::PathError(format!(
+            "{file_name} is not part of the quarantine bundle"
+        )));
+    }
+    fs::read(bundle_dir(quarantine_dir, tx_id).join(file_name)).map_err(|err| {
+        VerificationError::IOError(err, "Error reading quarantined file".to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_bundles_in_the_quarantine_dir() {
+        let quarantine_dir = Path::new("/var/quarantine");
+        for tx_id in ["../../etc", "/etc/passwd", "a/../../b", "", ".", ".."] {
+            let bundle = bundle_dir(quarantine_dir, tx_id);
+            assert_eq!(bundle.parent(), Some(quarantine_dir), "{tx_id}");
+        }
+    }
+
+    #[test]
+    fn separates_ids_that_sanitize_alike() {
+        let quarantine_dir = Path::new("/var/quarantine");
+        let ids = ["tx/1", "tx.1", "tx_1", "tx 1", "tx\u{e9}1"];
+        let mut bundles: Vec<PathBuf> = ids
+            .iter()
+            .map(|tx_id| bundle_dir(quarantine_dir, tx_id))
+            .collect();
+        bundles.sort();
+        bundles.dedup();
+        assert_eq!(bundles.len(), ids.len());
+        assert_eq!(
+            bundle_dir(quarantine_dir, "tx/1"),
+            bundle_dir(quarantine_dir, "tx/1")
+        );
+    }
+
+    #[test]
+    fn bounds_bundle_name_length() {
+        let quarantine_dir = Path::new("/var/quarantine");
+        let long_id = "\u{e9}".repeat(4096);
+        let bundle = bundle_dir(quarantine_dir, &long_id);
+        let name = bundle.file_name().unwrap().to_str().unwrap();
+        assert_eq!(name.len(), BUNDLE_PREFIX_LEN + 17);
+        assert_ne!(bundle, bundle_dir(quarantine_dir, &"\u{e9}".repeat(4097)));
+    }
+}
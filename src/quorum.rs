@@ -0,0 +1,652 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use log::{info, warn};
+use rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{oneshot, Mutex};
+
+use crate::attestation;
+use crate::config::{backend_name, Config};
+use crate::errors::VerificationError;
+use crate::models::{
+    Attestation, QuorumFile, QuorumRequest, QuorumResponse, QuorumResult, QuorumVote,
+    VerificationJob, VerificationOutcome, VerificationResult,
+};
+use crate::quarantine::bundle_dir;
+use crate::retention::unix_timestamp;
+use crate::sandbox;
+use crate::services;
+
+pub const QUORUM_DOMAIN: &str = "jugalbandi-quorum-v1";
+
+// a job a coordinator fanned out to this instance, verified by the worker
+// like local ones so peer requests share its concurrency limit
+pub struct PeerJob {
+    pub job: VerificationJob,
+    pub reply: oneshot::Sender<Result<VerificationResult, VerificationError>>,
+}
+
+// called by the worker, a job whose request already timed out is skipped
+pub async fn run_peer_job(peer_job: PeerJob, sandboxed: bool) {
+    if peer_job.reply.is_closed() {
+        return;
+    }
+    let verification_result = if sandboxed {
+        sandbox::verify_sandboxed(peer_job.job).await
+    } else {
+        services::verify_isolated(peer_job.job).await
+    };
+    let _ = peer_job.reply.send(verification_result);
+}
+
+// the signed message is the compact JSON array
+// [domain, tx_id, job, [[path, sha256], ...], issued_at]
+fn request_message(request: &QuorumRequest) -> Vec<u8> {
+    let files: Vec<(&String, &String)> = request
+        .files
+        .iter()
+        .map(|file| (&file.path, &file.sha256))
+        .collect();
+    serde_json::to_vec(&(
+        QUORUM_DOMAIN,
+        &request.tx_id,
+        &request.job,
+        files,
+        request.issued_at,
+    ))
+    .unwrap_or_default()
+}
+
+// only pinned coordinators are served, and only with requests issued within
+// the quorum timeout that ship every file the job reads
+fn check_request(request: &QuorumRequest, config: &Config) -> Result<(), VerificationError> {
+    if !config.quorum_coordinators.contains(&request.public_key) {
+        return Err(VerificationError::KeyError(format!(
+            "{} is not one of the QUORUM_COORDINATORS",
+            request.public_key
+        )));
+    }
+    if !attestation::verify_signature(
+        &request.public_key,
+        &request_message(request),
+        &request.signature,
+    ) {
+        return Err(VerificationError::KeyError(
+            "invalid quorum request signature".to_string(),
+        ));
+    }
+    let now = unix_timestamp();
+    if request.issued_at.saturating_add(config.quorum_timeout_secs) < now
+        || request.issued_at > now.saturating_add(config.quorum_timeout_secs)
+    {
+        return Err(VerificationError::KeyError(format!(
+            "quorum request issued at {} has expired",
+            request.issued_at
+        )));
+    }
+    let mut shipped: Vec<&str> = request
+        .files
+        .iter()
+        .map(|file| file.path.as_str())
+        .collect();
+    let mut inputs = request.job.input_files();
+    shipped.sort_unstable();
+    inputs.sort_unstable();
+    if shipped != inputs {
+        return Err(VerificationError::ParseError(
+            "quorum request does not ship the files of its job".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// writes the shipped files to peer_dir and queues the job pointed at them
+async fn verify_shipped(
+    request: &QuorumRequest,
+    peer_dir: &Path,
+    peer_queue: &Mutex<VecDeque<PeerJob>>,
+    config: &Config,
+) -> Result<
+    (
+        VerificationJob,
+        Result<VerificationResult, VerificationError>,
+    ),
+    VerificationError,
+> {
+    fs::create_dir_all(peer_dir).map_err(|err| {
+        VerificationError::IOError(err, format!("Error creating {}", peer_dir.display()))
+    })?;
+    let mut copies = Vec::new();
+    for (index, file) in request.files.iter().enumerate() {
+        let content = BASE64.decode(&file.content).map_err(|err| {
+            VerificationError::ParseError(format!("Error decoding {}: {err}", file.path))
+        })?;
+        if hex::encode(Sha256::digest(&content)) != file.sha256 {
+            return Err(VerificationError::ParseError(format!(
+                "{} does not match its sha256",
+                file.path
+            )));
+        }
+        let file_name = Path::new(&file.path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "file".to_string());
+        let copy = peer_dir.join(format!("{}-{}", index, file_name));
+        fs::write(&copy, content).map_err(|err| {
+            VerificationError::IOError(err, format!("Error writing {}", copy.display()))
+        })?;
+        copies.push((file.path.clone(), copy.to_string_lossy().to_string()));
+    }
+    let job = request.job.relocated(&copies).ok_or_else(|| {
+        VerificationError::ParseError("Error relocating the quorum job".to_string())
+    })?;
+
+    let (reply, answer) = oneshot::channel();
+    peer_queue.lock().await.push_back(PeerJob {
+        job: job.clone(),
+        reply,
+    });
+    match tokio::time::timeout(Duration::from_secs(config.quorum_timeout_secs), answer).await {
+        Ok(Ok(verification_result)) => Ok((job, verification_result)),
+        Ok(Err(_)) => Err(VerificationError::Quorum(
+            "the worker dropped the quorum job".to_string(),
+        )),
+        Err(_) => Err(VerificationError::Quorum(
+            "the quorum job timed out in the queue".to_string(),
+        )),
+    }
+}
+
+// a peer's verification of a job fanned out by a coordinator, run on copies
+// of the shipped files
+pub async fn verify_for_peer(
+    request: QuorumRequest,
+    peer_queue: &Mutex<VecDeque<PeerJob>>,
+) -> Result<Attestation, VerificationError> {
+    let config = Config::init();
+    check_request(&request, config)?;
+    let peer_dir = bundle_dir(
+        &std::env::temp_dir().join("jugalbandi-quorum"),
+        &format!("{}-{:016x}", request.tx_id, OsRng.next_u64()),
+    );
+    let verified = verify_shipped(&request, &peer_dir, peer_queue, config).await;
+    let attestation = verified.map(|(job, verification_result)| {
+        attestation::attest(
+            &request.tx_id,
+            backend_name(job.verify_type()),
+            Some(&job),
+            &verification_result,
+            unix_timestamp(),
+        )
+    });
+    if let Err(err) = fs::remove_dir_all(&peer_dir) {
+        warn!("Error removing {}: {:?}", peer_dir.display(), err);
+    }
+    let mut attestation = attestation?;
+    // quorum votes are always signed, batching only covers local results
+    if attestation.signature.is_none() {
+        attestation.signature = Some(attestation::sign(&attestation::message(&attestation)));
+    }
+    Ok(attestation)
+}
+
+async fn build_request(
+    tx_id: &str,
+    job: &VerificationJob,
+) -> Result<QuorumRequest, VerificationError> {
+    let mut files = Vec::new();
+    for path in job.input_files() {
+        let content = tokio::fs::read(&path)
+            .await
+            .map_err(|err| VerificationError::IOError(err, format!("Error reading {path}")))?;
+        files.push(QuorumFile {
+            sha256: hex::encode(Sha256::digest(&content)),
+            content: BASE64.encode(content),
+            path,
+        });
+    }
+    let mut request = QuorumRequest {
+        tx_id: tx_id.to_string(),
+        job: job.clone(),
+        files,
+        issued_at: unix_timestamp(),
+        public_key: attestation::public_key(),
+        signature: String::new(),
+    };
+    request.signature = attestation::sign(&request_message(&request));
+    Ok(request)
+}
+
+async fn request_vote(
+    client: &reqwest::Client,
+    peer: &str,
+    request: &QuorumRequest,
+) -> Result<Attestation, String> {
+    let url = format!("{}/quorum/verify", peer.trim_end_matches('/'));
+    let response = client
+        .post(&url)
+        .json(request)
+        .send()
+        .await
+        .map_err(|err| format!("Error reaching {url}: {err}"))?;
+    if !response.status().is_success() {
+        return Err(format!("{url} answered {}", response.status()));
+    }
+    let response: QuorumResponse = response
+        .json()
+        .await
+        .map_err(|err| format!("Error reading response from {url}: {err}"))?;
+    Ok(response.attestation)
+}
+
+// a vote only counts when it is signed by the key pinned for the peer and
+// attests the same submission this instance verified
+fn check_vote(
+    attestation: &Attestation,
+    public_key: &str,
+    local: &Attestation,
+) -> Result<bool, String> {
+    if attestation.public_key != public_key {
+        return Err(format!(
+            "signed by {} instead of {}",
+            attestation.public_key, public_key
+        ));
+    }
+    let signature = attestation
+        .signature
+        .as_deref()
+        .ok_or_else(|| "attestation is not signed".to_string())?;
+    if !attestation::verify_signature(public_key, &attestation::message(attestation), signature) {
+        return Err("invalid attestation signature".to_string());
+    }
+    if attestation.tx_id != local.tx_id
+        || attestation.program_id != local.program_id
+        || attestation.proof_hash != local.proof_hash
+    {
+        return Err("attests a different submission".to_string());
+    }
+    Ok(attestation.is_valid)
+}
+
+// counts the valid and invalid votes and settles on an outcome, votes that
+// were discarded or never arrived count for neither side
+fn tally(votes: &[QuorumVote], threshold: usize) -> (usize, usize, VerificationOutcome) {
+    let valid = votes
+        .iter()
+        .filter(|vote| vote.is_valid == Some(true))
+        .count();
+    let invalid = votes
+        .iter()
+        .filter(|vote| vote.is_valid == Some(false))
+        .count();
+    // with k at most half of n both sides can reach k, which is no agreement.
+    // When neither does, split votes are a disagreement and agreeing ones
+    // are too few to count
+    let outcome = match (valid >= threshold, invalid >= threshold) {
+        (true, false) => VerificationOutcome::Valid,
+        (false, true) => VerificationOutcome::Invalid,
+        (true, true) => VerificationOutcome::Disagreement,
+        (false, false) if valid > 0 && invalid > 0 => VerificationOutcome::Disagreement,
+        (false, false) => VerificationOutcome::NoQuorum,
+    };
+    (valid, invalid, outcome)
+}
+
+// fans the job out to the configured peers and counts this instance's own
+// result as one more vote, k of the n votes have to agree on valid or invalid
+pub async fn collect(tx_id: &str, job: &VerificationJob, local: &Attestation) -> QuorumResult {
+    let config = Config::init();
+    let threshold = config.quorum_threshold;
+    let client = match reqwest::Client::builder()
+        .timeout(Duration::from_secs(config.quorum_timeout_secs))
+        .build()
+    {
+        Ok(client) => client,
+        Err(err) => {
+            warn!("Error building quorum client: {:?}", err);
+            reqwest::Client::new()
+        }
+    };
+
+    let mut requests = tokio::task::JoinSet::new();
+    match build_request(tx_id, job).await {
+        Ok(request) => {
+            let request = Arc::new(request);
+            for (index, (peer, _)) in config.quorum_peers.iter().enumerate() {
+                let client = client.clone();
+                let peer = peer.clone();
+                let request = request.clone();
+                requests
+                    .spawn(async move { (index, request_vote(&client, &peer, &request).await) });
+            }
+        }
+        Err(err) => warn!("Error building quorum request for {}: {:?}", tx_id, err),
+    }
+    let mut votes = vec![QuorumVote {
+        peer: "self".to_string(),
+        is_valid: Some(local.is_valid),
+        attestation: Some(local.clone()),
+        error: None,
+    }];
+    let mut peer_votes: Vec<Option<QuorumVote>> = vec![None; config.quorum_peers.len()];
+    while let Some(joined) = requests.join_next().await {
+        let (index, answer) = match joined {
+            Ok(joined) => joined,
+            Err(err) => {
+                warn!("Quorum request task failed: {:?}", err);
+                continue;
+            }
+        };
+        let (peer, public_key) = &config.quorum_peers[index];
+        let checked = answer.and_then(|attestation| {
+            check_vote(&attestation, public_key, local).map(|is_valid| (attestation, is_valid))
+        });
+        peer_votes[index] = Some(match checked {
+            Ok((attestation, is_valid)) => QuorumVote {
+                peer: peer.clone(),
+                is_valid: Some(is_valid),
+                attestation: Some(attestation),
+                error: None,
+            },
+            Err(error) => {
+                warn!(
+                    "Discarding quorum vote of {} for {}: {}",
+                    peer, tx_id, error
+                );
+                QuorumVote {
+                    peer: peer.clone(),
+                    is_valid: None,
+                    attestation: None,
+                    error: Some(error),
+                }
+            }
+        });
+    }
+    votes.extend(
+        peer_votes
+            .into_iter()
+            .zip(&config.quorum_peers)
+            .map(|(vote, (peer, _))| {
+                vote.unwrap_or_else(|| QuorumVote {
+                    peer: peer.clone(),
+                    is_valid: None,
+                    attestation: None,
+                    error: Some("no answer".to_string()),
+                })
+            }),
+    );
+
+    let (valid, invalid, outcome) = tally(&votes, threshold);
+    info!(
+        "Quorum for {}: {} valid, {} invalid of {} votes, {} needed, {:?}",
+        tx_id,
+        valid,
+        invalid,
+        votes.len(),
+        threshold,
+        outcome
+    );
+    QuorumResult {
+        outcome,
+        threshold,
+        votes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    fn public_key_of(key: &SigningKey) -> String {
+        hex::encode(key.verifying_key().to_bytes())
+    }
+
+    fn sign_with(key: &SigningKey, message: &[u8]) -> String {
+        hex::encode(key.sign(message).to_bytes())
+    }
+
+    fn vote(is_valid: Option<bool>) -> QuorumVote {
+        QuorumVote {
+            peer: "peer".to_string(),
+            is_valid,
+            attestation: None,
+            error: is_valid.is_none().then(|| "no answer".to_string()),
+        }
+    }
+
+    #[test]
+    fn settles_on_an_outcome() {
+        use VerificationOutcome::*;
+        let (t, f) = (Some(true), Some(false));
+        let table: &[(&[Option<bool>], usize, VerificationOutcome)] = &[
+            // exactly k votes agree
+            (&[t, t, f], 2, Valid),
+            (&[f, f, t], 2, Invalid),
+            (&[t, t, None], 2, Valid),
+            (&[t], 1, Valid),
+            (&[f], 1, Invalid),
+            // unanimous
+            (&[t, t, t], 3, Valid),
+            (&[f, f, f], 3, Invalid),
+            // both sides reach k on a tie
+            (&[t, t, f, f], 2, Disagreement),
+            (&[t, f], 1, Disagreement),
+            // split votes where neither side reaches k
+            (&[t, t, f], 3, Disagreement),
+            (&[t, f, None], 2, Disagreement),
+            // agreeing votes, but too few of them
+            (&[t, t, None], 3, NoQuorum),
+            (&[f, None, None], 2, NoQuorum),
+            (&[None, None, None], 2, NoQuorum),
+        ];
+        for (votes, threshold, expected) in table {
+            let votes: Vec<QuorumVote> = votes.iter().copied().map(vote).collect();
+            let (valid, invalid, outcome) = tally(&votes, *threshold);
+            assert_eq!(outcome, *expected, "{votes:?} with k = {threshold}");
+            assert_eq!(
+                valid + invalid,
+                votes.iter().filter(|vote| vote.is_valid.is_some()).count()
+            );
+        }
+    }
+
+    fn attestation(key: &SigningKey, is_valid: bool) -> Attestation {
+        let mut attestation = Attestation {
+            tx_id: "tx-1".to_string(),
+            backend: "sp1".to_string(),
+            program_id: Some(hex::encode([1u8; 32])),
+            proof_hash: Some(hex::encode([2u8; 32])),
+            public_outputs_hash: hex::encode([3u8; 32]),
+            is_valid,
+            result: VerificationOutcome::from_valid(is_valid),
+            timestamp: 1_700_000_000,
+            public_key: public_key_of(key),
+            signature: None,
+        };
+        attestation.signature = Some(sign_with(key, &attestation::message(&attestation)));
+        attestation
+    }
+
+    #[test]
+    fn counts_votes_signed_by_the_pinned_key() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let local = attestation(&SigningKey::from_bytes(&[9u8; 32]), true);
+        assert_eq!(
+            check_vote(&attestation(&key, true), &public_key_of(&key), &local),
+            Ok(true)
+        );
+        // a vote against the local verdict still counts
+        assert_eq!(
+            check_vote(&attestation(&key, false), &public_key_of(&key), &local),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn discards_bad_votes() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let other_key = SigningKey::from_bytes(&[8u8; 32]);
+        let pinned = public_key_of(&key);
+        let local = attestation(&SigningKey::from_bytes(&[9u8; 32]), true);
+
+        let error = check_vote(&attestation(&other_key, true), &pinned, &local).unwrap_err();
+        assert!(error.starts_with("signed by"), "{error}");
+
+        let mut unsigned = attestation(&key, true);
+        unsigned.signature = None;
+        let error = check_vote(&unsigned, &pinned, &local).unwrap_err();
+        assert_eq!(error, "attestation is not signed");
+
+        // signed by another key but claiming the pinned one
+        let mut forged = attestation(&other_key, true);
+        forged.public_key = pinned.clone();
+        let error = check_vote(&forged, &pinned, &local).unwrap_err();
+        assert_eq!(error, "invalid attestation signature");
+
+        // the verdict flipped after signing
+        let mut flipped = attestation(&key, true);
+        flipped.is_valid = false;
+        let error = check_vote(&flipped, &pinned, &local).unwrap_err();
+        assert_eq!(error, "invalid attestation signature");
+
+        let mut elsewhere = attestation(&key, true);
+        elsewhere.proof_hash = Some(hex::encode([4u8; 32]));
+        elsewhere.signature = Some(sign_with(&key, &attestation::message(&elsewhere)));
+        let error = check_vote(&elsewhere, &pinned, &local).unwrap_err();
+        assert_eq!(error, "attests a different submission");
+    }
+
+    #[cfg(feature = "sp1")]
+    mod requests {
+        use super::*;
+        use crate::models::Sp1Proof;
+
+        const TIMEOUT_SECS: u64 = 60;
+
+        fn config(coordinators: &[&SigningKey]) -> Config {
+            let mut config = Config::init().clone();
+            config.quorum_coordinators =
+                coordinators.iter().map(|key| public_key_of(key)).collect();
+            config.quorum_timeout_secs = TIMEOUT_SECS;
+            config
+        }
+
+        fn file(path: &str) -> QuorumFile {
+            QuorumFile {
+                path: path.to_string(),
+                sha256: hex::encode(Sha256::digest(path)),
+                content: BASE64.encode(path),
+            }
+        }
+
+        fn request(key: &SigningKey, issued_at: u64, paths: &[&str]) -> QuorumRequest {
+            let mut request = QuorumRequest {
+                tx_id: "tx-1".to_string(),
+                job: VerificationJob::Sp1(Sp1Proof {
+                    proof_file_path: "/spool/proof".to_string(),
+                    elf_file_path: "/spool/elf".to_string(),
+                    version: "1.0.0".to_string(),
+                    envelope: None,
+                }),
+                files: paths.iter().map(|path| file(path)).collect(),
+                issued_at,
+                public_key: public_key_of(key),
+                signature: String::new(),
+            };
+            request.signature = sign_with(key, &request_message(&request));
+            request
+        }
+
+        const FILES: &[&str] = &["/spool/elf", "/spool/proof"];
+
+        #[test]
+        fn serves_pinned_coordinators() {
+            let key = SigningKey::from_bytes(&[7u8; 32]);
+            let other_key = SigningKey::from_bytes(&[8u8; 32]);
+            let now = unix_timestamp();
+            let request = request(&key, now, FILES);
+            assert!(check_request(&request, &config(&[&other_key, &key])).is_ok());
+
+            for config in [config(&[]), config(&[&other_key])] {
+                let err = check_request(&request, &config).unwrap_err();
+                assert!(
+                    matches!(&err, VerificationError::KeyError(message)
+                        if message.ends_with("is not one of the QUORUM_COORDINATORS")),
+                    "{err:?}"
+                );
+            }
+        }
+
+        #[test]
+        fn rejects_invalid_request_signatures() {
+            let key = SigningKey::from_bytes(&[7u8; 32]);
+            let other_key = SigningKey::from_bytes(&[8u8; 32]);
+            let config = config(&[&key]);
+            let now = unix_timestamp();
+
+            // signed by another key but claiming the pinned one
+            let mut forged = request(&other_key, now, FILES);
+            forged.public_key = public_key_of(&key);
+            // the job changed after signing
+            let mut retargeted = request(&key, now, FILES);
+            retargeted.tx_id = "tx-2".to_string();
+            let mut garbled = request(&key, now, FILES);
+            garbled.signature = "not hex".to_string();
+
+            for request in [forged, retargeted, garbled] {
+                let err = check_request(&request, &config).unwrap_err();
+                assert!(
+                    matches!(&err, VerificationError::KeyError(message)
+                        if message == "invalid quorum request signature"),
+                    "{err:?}"
+                );
+            }
+        }
+
+        #[test]
+        fn rejects_expired_requests() {
+            let key = SigningKey::from_bytes(&[7u8; 32]);
+            let config = config(&[&key]);
+            let now = unix_timestamp();
+            for issued_at in [now - TIMEOUT_SECS + 5, now + TIMEOUT_SECS - 5] {
+                assert!(check_request(&request(&key, issued_at, FILES), &config).is_ok());
+            }
+            for issued_at in [0, now - TIMEOUT_SECS - 5, now + TIMEOUT_SECS + 5] {
+                let err = check_request(&request(&key, issued_at, FILES), &config).unwrap_err();
+                assert!(
+                    matches!(&err, VerificationError::KeyError(message)
+                        if message.ends_with("has expired")),
+                    "{err:?}"
+                );
+            }
+        }
+
+        #[test]
+        fn requires_every_input_file() {
+            let key = SigningKey::from_bytes(&[7u8; 32]);
+            let config = config(&[&key]);
+            let now = unix_timestamp();
+            // shipped in any order
+            let reordered = request(&key, now, &["/spool/proof", "/spool/elf"]);
+            assert!(check_request(&reordered, &config).is_ok());
+
+            for paths in [
+                &["/spool/proof"][..],
+                &["/spool/elf", "/spool/proof", "/etc/passwd"],
+                &["/spool/elf", "/spool/elf"],
+            ] {
+                let err = check_request(&request(&key, now, paths), &config).unwrap_err();
+                assert!(
+                    matches!(&err, VerificationError::ParseError(_)),
+                    "{paths:?}: {err:?}"
+                );
+            }
+        }
+    }
+}
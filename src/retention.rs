@@ -0,0 +1,264 @@
+use log::{info, warn};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+
+use crate::config::Config;
+use crate::errors::VerificationError;
+use crate::models::{
+    RemovedFile, SweepReport, VerificationRecord, VerificationResult, VerificationStatus,
+    VerifyProof,
+};
+use crate::quarantine::quarantine;
+use crate::storage::{ProofStores, SPOOL_ROOTS};
+
+pub fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+// called once a proof has been verified, files of failed proofs are kept as a
+// quarantine bundle instead of being deleted
+pub fn release_files(
+    record: &VerificationRecord,
+    verification_result: &Result<VerificationResult, VerificationError>,
+) {
+    let config = Config::init();
+    let is_valid = matches!(verification_result, Ok(result) if result.is_valid);
+    if !is_valid && config.keep_invalid && !record.files.is_empty() {
+        if let Err(err) = quarantine(
+            &config.quarantine_dir,
+            record,
+            verification_result,
+            config.delete_files,
+        ) {
+            warn!(
+                "Error quarantining verification {}: {:?}",
+                record.tx_id, err
+            );
+        }
+        return;
+    }
+    if !config.delete_files {
+        return;
+    }
+    for file in &record.files {
+        let _ = fs::remove_file(file).map_err(|err| warn!("Error deleting file: {:?}", err));
+    }
+}
+
+// drops statuses older than STATUS_TTL_SECS, then the oldest ones until at
+// most STATUS_MAX_ENTRIES are left
+pub fn prune_statuses(statuses: &mut HashMap<String, VerificationStatus>, config: &Config) {
+    if config.status_ttl_secs > 0 {
+        let cutoff = unix_timestamp().saturating_sub(config.status_ttl_secs);
+        statuses.retain(|_, status| status.finished_at >= cutoff);
+    }
+    if config.status_max_entries > 0 && statuses.len() >= config.status_max_entries {
+        let mut finished: Vec<(u64, String)> = statuses
+            .iter()
+            .map(|(tx_id, status)| (status.finished_at, tx_id.clone()))
+            .collect();
+        finished.sort();
+        // leaves room for the status about to be inserted
+        let excess = statuses.len() + 1 - config.status_max_entries;
+        for (_, tx_id) in finished.into_iter().take(excess) {
+            statuses.remove(&tx_id);
+        }
+    }
+}
+
+pub fn move_file(source: &Path, target: &Path) -> std::io::Result<()> {
+    // rename does not work across filesystems
+    if fs::rename(source, target).is_err() {
+        fs::copy(source, target)?;
+        fs::remove_file(source)?;
+    }
+    Ok(())
+}
+
+struct SpoolFile {
+    bytes: u64,
+    modified: SystemTime,
+    // quarantine bundles are kept or evicted as a whole directory
+    bundle: bool,
+}
+
+fn collect_files(dir: &Path, skip: &HashSet<PathBuf>, files: &mut BTreeMap<PathBuf, SpoolFile>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            warn!("Error reading {}: {:?}", dir.display(), err);
+            return;
+        }
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if skip.contains(&path) {
+            continue;
+        }
+        // symlink_metadata so links out of the spool are never followed
+        let metadata = match fs::symlink_metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+        if metadata.is_dir() {
+            collect_files(&path, skip, files);
+        } else if metadata.is_file() {
+            files.insert(
+                path,
+                SpoolFile {
+                    bytes: metadata.len(),
+                    modified: metadata.modified().unwrap_or(UNIX_EPOCH),
+                    bundle: false,
+                },
+            );
+        }
+    }
+}
+
+// a bundle is as old as its newest file
+fn collect_bundles(quarantine_dir: &Path, files: &mut BTreeMap<PathBuf, SpoolFile>) {
+    let entries = match fs::read_dir(quarantine_dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            warn!("Error reading {}: {:?}", quarantine_dir.display(), err);
+            return;
+        }
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        match fs::symlink_metadata(&path) {
+            Ok(metadata) if metadata.is_dir() => {}
+            _ => continue,
+        }
+        let mut bundle_files = BTreeMap::new();
+        collect_files(&path, &HashSet::new(), &mut bundle_files);
+        files.insert(
+            path,
+            SpoolFile {
+                bytes: bundle_files.values().map(|file| file.bytes).sum(),
+                modified: bundle_files
+                    .values()
+                    .map(|file| file.modified)
+                    .max()
+                    .unwrap_or(UNIX_EPOCH),
+                bundle: true,
+            },
+        );
+    }
+}
+
+// live_files are the files of jobs that are still queued or being verified,
+// they are neither counted nor evicted
+pub fn sweep(config: &Config, live_files: &HashSet<PathBuf>) -> SweepReport {
+    let started_at = unix_timestamp();
+    let mut skip = live_files.clone();
+    if let Ok(quarantine_dir) = fs::canonicalize(&config.quarantine_dir) {
+        skip.insert(quarantine_dir);
+    }
+    let mut files = BTreeMap::new();
+    for root in SPOOL_ROOTS.iter().flatten() {
+        collect_files(root, &skip, &mut files);
+    }
+    if config.quarantine_dir.is_dir() {
+        collect_bundles(&config.quarantine_dir, &mut files);
+    }
+
+    let now = SystemTime::now();
+    let max_age = Duration::from_secs(config.retention_max_age_secs);
+    let mut evictions = Vec::new();
+    let mut remaining = Vec::new();
+    for (path, file) in files {
+        let age = now.duration_since(file.modified).unwrap_or_default();
+        if config.retention_max_age_secs > 0 && age > max_age {
+            evictions.push((path, file, "age"));
+        } else {
+            remaining.push((path, file));
+        }
+    }
+
+    // evict the oldest files until the spool fits
+    let mut remaining_bytes: u64 = remaining.iter().map(|(_, file)| file.bytes).sum();
+    if config.retention_max_spool_bytes > 0 {
+        remaining.sort_by_key(|(_, file)| file.modified);
+        let mut kept = Vec::new();
+        for (path, file) in remaining {
+            if remaining_bytes > config.retention_max_spool_bytes {
+                remaining_bytes -= file.bytes;
+                evictions.push((path, file, "size"));
+            } else {
+                kept.push((path, file));
+            }
+        }
+        remaining = kept;
+    }
+
+    let mut remaining_files = remaining.len();
+    let mut removed = Vec::new();
+    let mut freed_bytes = 0;
+    for (path, file, reason) in evictions {
+        let evicted = if file.bundle {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        match evicted {
+            Ok(()) => {
+                freed_bytes += file.bytes;
+                removed.push(RemovedFile {
+                    path: path.to_string_lossy().to_string(),
+                    bytes: file.bytes,
+                    reason: reason.to_string(),
+                });
+            }
+            Err(err) => {
+                warn!("Error evicting {}: {:?}", path.display(), err);
+                remaining_files += 1;
+                remaining_bytes += file.bytes;
+            }
+        }
+    }
+
+    SweepReport {
+        started_at,
+        finished_at: unix_timestamp(),
+        removed,
+        freed_bytes,
+        remaining_files,
+        remaining_bytes,
+    }
+}
+
+pub async fn run_sweeper(
+    queue: Arc<Mutex<VecDeque<VerifyProof>>>,
+    in_flight: Arc<Mutex<HashMap<String, VerifyProof>>>,
+    proof_stores: ProofStores,
+    last_report: Arc<Mutex<Option<SweepReport>>>,
+) {
+    loop {
+        let config = Config::init();
+        tokio::time::sleep(Duration::from_secs(config.retention_sweep_interval_secs)).await;
+        let live_files = proof_stores.queued_files(&queue, &in_flight).await;
+        let report = match tokio::task::spawn_blocking(move || sweep(config, &live_files)).await {
+            Ok(report) => report,
+            Err(err) => {
+                warn!("Retention sweep failed: {:?}", err);
+                continue;
+            }
+        };
+        info!(
+            "Retention sweep removed {} files ({} bytes), {} files ({} bytes) remain",
+            report.removed.len(),
+            report.freed_bytes,
+            report.remaining_files,
+            report.remaining_bytes
+        );
+        *last_report.lock().await = Some(report);
+    }
+}
@@ -4,10 +4,108 @@ use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+use crate::attestation;
+use crate::audit;
+use crate::batch;
+#[cfg(feature = "miden")]
+use crate::config::VERIFY_TYPE_MIDEN;
+#[cfg(feature = "risc0")]
+use crate::config::VERIFY_TYPE_RISC0;
+#[cfg(feature = "sp1")]
+use crate::config::VERIFY_TYPE_SP1;
+use crate::config::{backend_name, Config};
+#[cfg(any(
+    feature = "miden",
+    feature = "risc0",
+    feature = "groth16",
+    feature = "plonky2",
+    feature = "halo2"
+))]
+use crate::models::RegistrationResult;
 use crate::models::{
-    JoltProof, MidenProof, Ping, PingSingle, Ports, ProodDataRisc0, ProofDataMiden, ProofDataSP1,
-    Risc0Proof, Sp1Proof, SubmitionResult, VerifyProof,
+    AttestationKey, AuditRange, Backends, Ping, PingSingle, Ports, QuorumRequest, QuorumResponse,
+    ShadowStats, SubmitionResult, SweepReport, VerificationStatus, VerifyProof,
 };
+#[cfg(feature = "groth16")]
+use crate::models::{Groth16Key, Groth16KeyInfo, Groth16Proof, ProofDataGroth16};
+#[cfg(feature = "halo2")]
+use crate::models::{Halo2Keys, Halo2KeysInfo, Halo2Proof, ProofDataHalo2};
+#[cfg(feature = "jolt")]
+use crate::models::{JoltProof, ProofDataJolt};
+#[cfg(feature = "miden")]
+use crate::models::{MidenProgram, MidenProgramInfo, MidenProof, ProofDataMiden};
+#[cfg(feature = "plonky2")]
+use crate::models::{Plonky2Circuit, Plonky2CircuitInfo, Plonky2Proof, ProofDataPlonky2};
+#[cfg(feature = "risc0")]
+use crate::models::{ProodDataRisc0, Risc0Program, Risc0ProgramInfo, Risc0Proof};
+#[cfg(feature = "sp1")]
+use crate::models::{ProofDataSP1, Sp1Proof};
+use crate::quarantine;
+use crate::quorum;
+use crate::services;
+#[cfg(feature = "groth16")]
+use crate::services::groth16_verifier::{self, Groth16Format};
+#[cfg(feature = "halo2")]
+use crate::services::halo2_verifier;
+use crate::services::helpers::validate_spool_path;
+#[cfg(feature = "miden")]
+use crate::services::miden_verifier;
+#[cfg(feature = "plonky2")]
+use crate::services::plonky2_verifier;
+#[cfg(feature = "risc0-v1")]
+use crate::services::risc0_v1_verifier;
+#[cfg(feature = "risc0")]
+use crate::services::risc0_verifier;
+use crate::storage::ProofStores;
+#[cfg(feature = "jolt")]
+use crate::storage::JOLT_HASHMAP;
+#[cfg(feature = "sp1")]
+use crate::storage::SP1_HASHMAP;
+#[cfg(feature = "groth16")]
+use crate::storage::{GROTH16_HASHMAP, GROTH16_KEYS};
+#[cfg(feature = "halo2")]
+use crate::storage::{HALO2_HASHMAP, HALO2_KEYS};
+#[cfg(feature = "miden")]
+use crate::storage::{MIDEN_HASHMAP, MIDEN_PROGRAMS};
+#[cfg(feature = "plonky2")]
+use crate::storage::{PLONKY2_CIRCUITS, PLONKY2_HASHMAP};
+#[cfg(feature = "risc0")]
+use crate::storage::{RISC0_HASHMAP, RISC0_PROGRAMS};
+
+// registers the shared state and routes of the backends compiled into this binary
+pub fn configure_backends(cfg: &mut web::ServiceConfig) {
+    #[cfg(feature = "sp1")]
+    cfg.app_data(web::Data::new(SP1_HASHMAP.clone()))
+        .service(verify_sp1);
+    #[cfg(feature = "miden")]
+    cfg.app_data(web::Data::new(MIDEN_HASHMAP.clone()))
+        .app_data(web::Data::new(MIDEN_PROGRAMS.clone()))
+        .service(register_miden)
+        .service(verify_miden);
+    #[cfg(feature = "risc0")]
+    cfg.app_data(web::Data::new(RISC0_HASHMAP.clone()))
+        .app_data(web::Data::new(RISC0_PROGRAMS.clone()))
+        .service(register_risc0)
+        .service(verify_risc0);
+    #[cfg(feature = "jolt")]
+    cfg.app_data(web::Data::new(JOLT_HASHMAP.clone()))
+        .service(verify_jolt);
+    #[cfg(feature = "groth16")]
+    cfg.app_data(web::Data::new(GROTH16_HASHMAP.clone()))
+        .app_data(web::Data::new(GROTH16_KEYS.clone()))
+        .service(register_groth16)
+        .service(verify_groth16);
+    #[cfg(feature = "plonky2")]
+    cfg.app_data(web::Data::new(PLONKY2_HASHMAP.clone()))
+        .app_data(web::Data::new(PLONKY2_CIRCUITS.clone()))
+        .service(register_plonky2)
+        .service(verify_plonky2);
+    #[cfg(feature = "halo2")]
+    cfg.app_data(web::Data::new(HALO2_HASHMAP.clone()))
+        .app_data(web::Data::new(HALO2_KEYS.clone()))
+        .service(register_halo2)
+        .service(verify_halo2);
+}
 
 #[get("/")]
 async fn hello() -> impl Responder {
@@ -46,71 +144,699 @@ async fn ping_single() -> impl Responder {
     HttpResponse::Ok().json(PingSingle { success: true })
 }
 
+#[get("/backends")]
+async fn backends() -> impl Responder {
+    HttpResponse::Ok().json(Backends {
+        backends: services::backends(),
+    })
+}
+
+#[get("/retention/report")]
+async fn retention_report(
+    last_report: web::Data<Arc<Mutex<Option<SweepReport>>>>,
+) -> impl Responder {
+    let last_report = last_report.lock().await;
+    HttpResponse::Ok().json(&*last_report)
+}
+
+#[get("/shadow/stats")]
+async fn shadow_stats(
+    shadow_stats: web::Data<Arc<Mutex<HashMap<String, ShadowStats>>>>,
+) -> impl Responder {
+    let shadow_stats = shadow_stats.lock().await;
+    HttpResponse::Ok().json(&*shadow_stats)
+}
+
+#[get("/status/{tx_id}")]
+async fn status(
+    statuses: web::Data<Arc<Mutex<HashMap<String, VerificationStatus>>>>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let tx_id = path.into_inner();
+    let statuses = statuses.lock().await;
+    match statuses.get(&tx_id) {
+        Some(status) => HttpResponse::Ok().json(status),
+        None => HttpResponse::NotFound().finish(),
+    }
+}
+
+#[get("/batches/{batch_id}")]
+async fn batch_commitment(path: web::Path<u64>) -> impl Responder {
+    let config = Config::init();
+    let batch_id = path.into_inner();
+    let sealed = web::block(move || batch::load_batch(&config.batch_dir, batch_id)).await;
+    match sealed {
+        Ok(Ok(Some(sealed))) => HttpResponse::Ok().json(sealed.commitment),
+        Ok(Ok(None)) => HttpResponse::NotFound().finish(),
+        Ok(Err(err)) => {
+            warn!("Error reading batch {}: {:?}", batch_id, err);
+            HttpResponse::InternalServerError().finish()
+        }
+        Err(err) => {
+            warn!("Error reading batch {}: {:?}", batch_id, err);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+#[get("/audit/verify")]
+async fn audit_verify() -> impl Responder {
+    let config = Config::init();
+    let public_key = attestation::public_key();
+    let report = web::block(move || audit::verify_chain(&config.audit_log_file, &public_key)).await;
+    match report {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(err) => {
+            warn!("Error verifying audit log: {:?}", err);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+// the latest signed head, for anchoring the chain outside this host
+#[get("/audit/head")]
+async fn audit_head() -> impl Responder {
+    let config = Config::init();
+    let head = web::block(move || audit::read_head(&config.audit_log_file)).await;
+    match head {
+        Ok(Ok(Some(head))) => HttpResponse::Ok().json(head),
+        Ok(Ok(None)) => HttpResponse::NotFound().finish(),
+        Ok(Err(err)) => {
+            warn!("Error reading audit head: {:?}", err);
+            HttpResponse::InternalServerError().finish()
+        }
+        Err(err) => {
+            warn!("Error reading audit head: {:?}", err);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+#[get("/audit/entries")]
+async fn audit_entries(range: web::Query<AuditRange>) -> impl Responder {
+    let config = Config::init();
+    let AuditRange { from, to } = range.into_inner();
+    let entries =
+        web::block(move || audit::export(&config.audit_log_file, from, to.unwrap_or(u64::MAX)))
+            .await;
+    match entries {
+        Ok(Ok(entries)) => HttpResponse::Ok().json(entries),
+        Ok(Err(err)) => {
+            warn!("Error exporting audit log: {:?}", err);
+            HttpResponse::InternalServerError().finish()
+        }
+        Err(err) => {
+            warn!("Error exporting audit log: {:?}", err);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+// registered as a resource in main, quorum requests carry the job's files
+// and need a larger body limit
+pub async fn quorum_verify(
+    peer_queue: web::Data<Arc<Mutex<VecDeque<quorum::PeerJob>>>>,
+    data: web::Json<QuorumRequest>,
+) -> impl Responder {
+    let request = data.into_inner();
+    let tx_id = request.tx_id.clone();
+    match quorum::verify_for_peer(request, &peer_queue).await {
+        Ok(attestation) => HttpResponse::Ok().json(QuorumResponse { attestation }),
+        Err(err) => {
+            warn!("Rejecting quorum request {}: {:?}", tx_id, err);
+            HttpResponse::BadRequest().finish()
+        }
+    }
+}
+
+#[get("/attestation/public-key")]
+async fn attestation_public_key() -> impl Responder {
+    HttpResponse::Ok().json(AttestationKey {
+        algorithm: "ed25519".to_string(),
+        public_key: attestation::public_key(),
+        evm_address: Config::init()
+            .evm_attestation
+            .then(attestation::evm_address),
+    })
+}
+
+#[get("/quarantine/{tx_id}")]
+async fn quarantine_manifest(path: web::Path<String>) -> impl Responder {
+    let tx_id = path.into_inner();
+    let config = Config::init();
+    match quarantine::read_manifest(&config.quarantine_dir, &tx_id) {
+        Ok(manifest) => HttpResponse::Ok().json(manifest),
+        Err(err) => {
+            warn!("Error reading quarantine bundle {}: {:?}", tx_id, err);
+            HttpResponse::NotFound().finish()
+        }
+    }
+}
+
+#[get("/quarantine/{tx_id}/{file_name}")]
+async fn quarantine_file(path: web::Path<(String, String)>) -> impl Responder {
+    let (tx_id, file_name) = path.into_inner();
+    let config = Config::init();
+    match quarantine::read_file(&config.quarantine_dir, &tx_id, &file_name) {
+        Ok(bytes) => HttpResponse::Ok()
+            .content_type("application/octet-stream")
+            .insert_header((
+                "Content-Disposition",
+                format!("attachment; filename=\"{}\"", file_name),
+            ))
+            .body(bytes),
+        Err(err) => {
+            warn!(
+                "Error reading quarantined file {}/{}: {:?}",
+                tx_id, file_name, err
+            );
+            HttpResponse::NotFound().finish()
+        }
+    }
+}
+
+#[cfg(feature = "sp1")]
 #[post("/sp1-verify")]
 async fn verify_sp1(
     sp1_hashmap: web::Data<Arc<Mutex<HashMap<String, Sp1Proof>>>>,
     data: web::Json<ProofDataSP1>,
 ) -> impl Responder {
-    let mut sp1_hashmap = sp1_hashmap.lock().await;
     let proof_data = data.into_inner();
+    let (proof_file_path, elf_file_path) = match (
+        validate_spool_path(&proof_data.proof_file_path),
+        validate_spool_path(&proof_data.elf_file_path),
+    ) {
+        (Ok(proof_file_path), Ok(elf_file_path)) => (proof_file_path, elf_file_path),
+        (Err(err), _) | (_, Err(err)) => {
+            warn!("Rejecting SP1 proof {}: {:?}", proof_data.tx_id, err);
+            return HttpResponse::Ok().json(SubmitionResult {
+                is_submitted: false,
+            });
+        }
+    };
+    let version = match services::resolve_version(VERIFY_TYPE_SP1, proof_data.version.as_deref()) {
+        Ok(version) => version,
+        Err(err) => {
+            warn!("Rejecting SP1 proof {}: {:?}", proof_data.tx_id, err);
+            return HttpResponse::Ok().json(SubmitionResult {
+                is_submitted: false,
+            });
+        }
+    };
+    let mut sp1_hashmap = sp1_hashmap.lock().await;
     sp1_hashmap.insert(
         proof_data.tx_id.clone(),
         Sp1Proof {
-            proof_file_path: proof_data.proof_file_path.clone(),
-            elf_file_path: proof_data.elf_file_path.clone(),
+            proof_file_path,
+            elf_file_path,
+            version: version.to_string(),
+            envelope: proof_data.envelope,
         },
     );
     HttpResponse::Ok().json(SubmitionResult { is_submitted: true })
 }
 
+#[cfg(feature = "jolt")]
 #[post("/jolt-verify")]
 async fn verify_jolt(
     jolt_hashmap: web::Data<Arc<Mutex<HashMap<String, JoltProof>>>>,
-    data: web::Json<ProofDataSP1>,
+    data: web::Json<ProofDataJolt>,
 ) -> impl Responder {
-    let mut jolt_hashmap = jolt_hashmap.lock().await;
     let proof_data = data.into_inner();
+    let (proof_file_path, elf_file_path) = match (
+        validate_spool_path(&proof_data.proof_file_path),
+        validate_spool_path(&proof_data.elf_file_path),
+    ) {
+        (Ok(proof_file_path), Ok(elf_file_path)) => (proof_file_path, elf_file_path),
+        (Err(err), _) | (_, Err(err)) => {
+            warn!("Rejecting Jolt proof {}: {:?}", proof_data.tx_id, err);
+            return HttpResponse::Ok().json(SubmitionResult {
+                is_submitted: false,
+            });
+        }
+    };
+    let mut jolt_hashmap = jolt_hashmap.lock().await;
     jolt_hashmap.insert(
         proof_data.tx_id.clone(),
         JoltProof {
-            proof_file_path: proof_data.proof_file_path.clone(),
-            elf_file_path: proof_data.elf_file_path.clone(),
+            proof_file_path,
+            elf_file_path,
+            envelope: proof_data.envelope,
+        },
+    );
+    HttpResponse::Ok().json(SubmitionResult { is_submitted: true })
+}
+
+#[cfg(feature = "groth16")]
+#[post("/groth16-register")]
+async fn register_groth16(
+    groth16_keys: web::Data<Arc<Mutex<HashMap<String, Groth16KeyInfo>>>>,
+    data: web::Json<Groth16Key>,
+) -> impl Responder {
+    let mut key = data.into_inner();
+    let registered = validate_spool_path(&key.vk_file_path).and_then(|vk_file_path| {
+        key.vk_file_path = vk_file_path;
+        groth16_verifier::register_key(&key)
+    });
+    match registered {
+        Ok(key_info) => {
+            info!(
+                "Registered Groth16 verifying key {} with hash {}",
+                key.key_id, key_info.vk_hash
+            );
+            let vk_hash = key_info.vk_hash.clone();
+            groth16_keys.lock().await.insert(key.key_id, key_info);
+            HttpResponse::Ok().json(RegistrationResult {
+                is_registered: true,
+                program_hash: Some(vk_hash),
+            })
+        }
+        Err(err) => {
+            warn!("Error registering Groth16 verifying key: {:?}", err);
+            HttpResponse::Ok().json(RegistrationResult {
+                is_registered: false,
+                program_hash: None,
+            })
+        }
+    }
+}
+
+#[cfg(feature = "groth16")]
+#[post("/groth16-verify")]
+async fn verify_groth16(
+    groth16_hashmap: web::Data<Arc<Mutex<HashMap<String, Groth16Proof>>>>,
+    groth16_keys: web::Data<Arc<Mutex<HashMap<String, Groth16KeyInfo>>>>,
+    data: web::Json<ProofDataGroth16>,
+) -> impl Responder {
+    let proof_data = data.into_inner();
+    let proof_file_path = match validate_spool_path(&proof_data.proof_file_path) {
+        Ok(proof_file_path) => proof_file_path,
+        Err(err) => {
+            warn!("Rejecting Groth16 proof {}: {:?}", proof_data.tx_id, err);
+            return HttpResponse::Ok().json(SubmitionResult {
+                is_submitted: false,
+            });
+        }
+    };
+    let key_info = match groth16_keys.lock().await.get(&proof_data.key_id) {
+        Some(key_info) => key_info.clone(),
+        None => {
+            warn!(
+                "Rejecting Groth16 proof {}: verifying key {} is not registered",
+                proof_data.tx_id, proof_data.key_id
+            );
+            return HttpResponse::Ok().json(SubmitionResult {
+                is_submitted: false,
+            });
+        }
+    };
+    if let Some(Err(err)) = proof_data
+        .format
+        .as_deref()
+        .map(str::parse::<Groth16Format>)
+    {
+        warn!("Rejecting Groth16 proof {}: {}", proof_data.tx_id, err);
+        return HttpResponse::Ok().json(SubmitionResult {
+            is_submitted: false,
+        });
+    }
+    let mut groth16_hashmap = groth16_hashmap.lock().await;
+    groth16_hashmap.insert(
+        proof_data.tx_id.clone(),
+        Groth16Proof {
+            vk: key_info.vk,
+            vk_hash: key_info.vk_hash,
+            proof_file_path,
+            public_inputs: proof_data.public_inputs,
+            format: proof_data.format,
+            envelope: proof_data.envelope,
         },
     );
     HttpResponse::Ok().json(SubmitionResult { is_submitted: true })
 }
 
+#[cfg(feature = "plonky2")]
+#[post("/plonky2-register")]
+async fn register_plonky2(
+    plonky2_circuits: web::Data<Arc<Mutex<HashMap<String, Plonky2CircuitInfo>>>>,
+    data: web::Json<Plonky2Circuit>,
+) -> impl Responder {
+    let mut circuit = data.into_inner();
+    let registered =
+        validate_spool_path(&circuit.common_data_file_path).and_then(|common_data_file_path| {
+            circuit.common_data_file_path = common_data_file_path;
+            circuit.verifier_data_file_path =
+                validate_spool_path(&circuit.verifier_data_file_path)?;
+            plonky2_verifier::register_circuit(&circuit)
+        });
+    match registered {
+        Ok(circuit_info) => {
+            info!(
+                "Registered Plonky2 circuit {} with digest {}",
+                circuit.circuit_id, circuit_info.circuit_digest
+            );
+            let circuit_digest = circuit_info.circuit_digest.clone();
+            plonky2_circuits
+                .lock()
+                .await
+                .insert(circuit.circuit_id, circuit_info);
+            HttpResponse::Ok().json(RegistrationResult {
+                is_registered: true,
+                program_hash: Some(circuit_digest),
+            })
+        }
+        Err(err) => {
+            warn!("Error registering Plonky2 circuit: {:?}", err);
+            HttpResponse::Ok().json(RegistrationResult {
+                is_registered: false,
+                program_hash: None,
+            })
+        }
+    }
+}
+
+#[cfg(feature = "plonky2")]
+#[post("/plonky2-verify")]
+async fn verify_plonky2(
+    plonky2_hashmap: web::Data<Arc<Mutex<HashMap<String, Plonky2Proof>>>>,
+    plonky2_circuits: web::Data<Arc<Mutex<HashMap<String, Plonky2CircuitInfo>>>>,
+    data: web::Json<ProofDataPlonky2>,
+) -> impl Responder {
+    let proof_data = data.into_inner();
+    let proof_file_path = match validate_spool_path(&proof_data.proof_file_path) {
+        Ok(proof_file_path) => proof_file_path,
+        Err(err) => {
+            warn!("Rejecting Plonky2 proof {}: {:?}", proof_data.tx_id, err);
+            return HttpResponse::Ok().json(SubmitionResult {
+                is_submitted: false,
+            });
+        }
+    };
+    let circuit_info = match plonky2_circuits.lock().await.get(&proof_data.circuit_id) {
+        Some(circuit_info) => circuit_info.clone(),
+        None => {
+            warn!(
+                "Rejecting Plonky2 proof {}: circuit {} is not registered",
+                proof_data.tx_id, proof_data.circuit_id
+            );
+            return HttpResponse::Ok().json(SubmitionResult {
+                is_submitted: false,
+            });
+        }
+    };
+    let mut plonky2_hashmap = plonky2_hashmap.lock().await;
+    plonky2_hashmap.insert(
+        proof_data.tx_id.clone(),
+        Plonky2Proof {
+            common_data: circuit_info.common_data,
+            verifier_data: circuit_info.verifier_data,
+            circuit_digest: circuit_info.circuit_digest,
+            proof_file_path,
+            envelope: proof_data.envelope,
+        },
+    );
+    HttpResponse::Ok().json(SubmitionResult { is_submitted: true })
+}
+
+#[cfg(feature = "halo2")]
+#[post("/halo2-register")]
+async fn register_halo2(
+    halo2_keys: web::Data<Arc<Mutex<HashMap<String, Halo2KeysInfo>>>>,
+    data: web::Json<Halo2Keys>,
+) -> impl Responder {
+    let mut keys = data.into_inner();
+    let registered = validate_spool_path(&keys.params_file_path).and_then(|params_file_path| {
+        keys.params_file_path = params_file_path;
+        keys.vk_file_path = validate_spool_path(&keys.vk_file_path)?;
+        halo2_verifier::register_keys(&keys)
+    });
+    match registered {
+        Ok(keys_info) => {
+            info!(
+                "Registered Halo2 keys {} for circuit {} with verifying key hash {}",
+                keys.key_id, keys_info.circuit, keys_info.vk_hash
+            );
+            let vk_hash = keys_info.vk_hash.clone();
+            halo2_keys.lock().await.insert(keys.key_id, keys_info);
+            HttpResponse::Ok().json(RegistrationResult {
+                is_registered: true,
+                program_hash: Some(vk_hash),
+            })
+        }
+        Err(err) => {
+            warn!("Error registering Halo2 keys: {:?}", err);
+            HttpResponse::Ok().json(RegistrationResult {
+                is_registered: false,
+                program_hash: None,
+            })
+        }
+    }
+}
+
+#[cfg(feature = "halo2")]
+#[post("/halo2-verify")]
+async fn verify_halo2(
+    halo2_hashmap: web::Data<Arc<Mutex<HashMap<String, Halo2Proof>>>>,
+    halo2_keys: web::Data<Arc<Mutex<HashMap<String, Halo2KeysInfo>>>>,
+    data: web::Json<ProofDataHalo2>,
+) -> impl Responder {
+    let proof_data = data.into_inner();
+    let proof_file_path = match validate_spool_path(&proof_data.proof_file_path) {
+        Ok(proof_file_path) => proof_file_path,
+        Err(err) => {
+            warn!("Rejecting Halo2 proof {}: {:?}", proof_data.tx_id, err);
+            return HttpResponse::Ok().json(SubmitionResult {
+                is_submitted: false,
+            });
+        }
+    };
+    let keys_info = match halo2_keys.lock().await.get(&proof_data.key_id) {
+        Some(keys_info) => keys_info.clone(),
+        None => {
+            warn!(
+                "Rejecting Halo2 proof {}: keys {} are not registered",
+                proof_data.tx_id, proof_data.key_id
+            );
+            return HttpResponse::Ok().json(SubmitionResult {
+                is_submitted: false,
+            });
+        }
+    };
+    let mut halo2_hashmap = halo2_hashmap.lock().await;
+    halo2_hashmap.insert(
+        proof_data.tx_id.clone(),
+        Halo2Proof {
+            circuit: keys_info.circuit,
+            params_file_path: keys_info.params_file_path,
+            vk_file_path: keys_info.vk_file_path,
+            vk_hash: keys_info.vk_hash,
+            proof_file_path,
+            instances: proof_data.instances,
+            envelope: proof_data.envelope,
+        },
+    );
+    HttpResponse::Ok().json(SubmitionResult { is_submitted: true })
+}
+
+#[cfg(feature = "miden")]
+#[post("/miden-register")]
+async fn register_miden(
+    miden_programs: web::Data<Arc<Mutex<HashMap<String, MidenProgramInfo>>>>,
+    data: web::Json<MidenProgram>,
+) -> impl Responder {
+    let program = data.into_inner();
+    let format = program.format.as_deref().unwrap_or("masm");
+    let envelope = program
+        .envelope
+        .unwrap_or(Config::init().envelope_for(VERIFY_TYPE_MIDEN));
+    let compiled = validate_spool_path(&program.program_file_path).and_then(|program_file_path| {
+        miden_verifier::compile_program(&program_file_path, format, envelope)
+    });
+    match compiled {
+        Ok(program_info) => {
+            info!(
+                "Registered Miden program {} with hash {}",
+                program.program_name, program_info.program_hash
+            );
+            let program_hash = program_info.program_hash.clone();
+            let mut miden_programs = miden_programs.lock().await;
+            miden_programs.insert(program.program_name, program_info);
+            HttpResponse::Ok().json(RegistrationResult {
+                is_registered: true,
+                program_hash: Some(program_hash),
+            })
+        }
+        Err(err) => {
+            warn!("Error registering Miden program: {:?}", err);
+            HttpResponse::Ok().json(RegistrationResult {
+                is_registered: false,
+                program_hash: None,
+            })
+        }
+    }
+}
+
+#[cfg(feature = "miden")]
 #[post("/miden-verify")]
 async fn verify_miden(
     miden_hashmap: web::Data<Arc<Mutex<HashMap<String, MidenProof>>>>,
+    miden_programs: web::Data<Arc<Mutex<HashMap<String, MidenProgramInfo>>>>,
     data: web::Json<ProofDataMiden>,
 ) -> impl Responder {
-    let mut miden_hashmap = miden_hashmap.lock().await;
     let proof_data = data.into_inner();
+    let proof_file_path = match validate_spool_path(&proof_data.proof_file_path) {
+        Ok(proof_file_path) => proof_file_path,
+        Err(err) => {
+            warn!("Rejecting Miden proof {}: {:?}", proof_data.tx_id, err);
+            return HttpResponse::Ok().json(SubmitionResult {
+                is_submitted: false,
+            });
+        }
+    };
+    let miden_programs = miden_programs.lock().await;
+    let (program_hash, kernel_procedures) = match miden_verifier::resolve_program(
+        &miden_programs,
+        proof_data.program_name.as_ref(),
+        proof_data.program_hash.as_ref(),
+        proof_data.kernel_procedures.as_ref(),
+    ) {
+        Ok(resolved) => resolved,
+        Err(err) => {
+            warn!("Rejecting Miden proof {}: {:?}", proof_data.tx_id, err);
+            return HttpResponse::Ok().json(SubmitionResult {
+                is_submitted: false,
+            });
+        }
+    };
+    let mut miden_hashmap = miden_hashmap.lock().await;
     miden_hashmap.insert(
         proof_data.tx_id.clone(),
         MidenProof {
-            program_hash: proof_data.program_hash.clone(),
+            program_hash,
             inputs_stack: proof_data.inputs_stack.clone(),
             outputs_stack: proof_data.outputs_stack.clone(),
-            proof_file_path: proof_data.proof_file_path.clone(),
+            proof_file_path,
+            kernel_procedures,
+            envelope: proof_data.envelope,
         },
     );
     HttpResponse::Ok().json(SubmitionResult { is_submitted: true })
 }
 
+#[cfg(feature = "risc0")]
+#[post("/risc0-register")]
+async fn register_risc0(
+    risc0_programs: web::Data<Arc<Mutex<HashMap<String, Risc0ProgramInfo>>>>,
+    data: web::Json<Risc0Program>,
+) -> impl Responder {
+    let program = data.into_inner();
+    let envelope = program
+        .envelope
+        .unwrap_or(Config::init().envelope_for(VERIFY_TYPE_RISC0));
+    let registered = services::resolve_version(VERIFY_TYPE_RISC0, program.version.as_deref())
+        .and_then(|version| {
+            let elf_file_path = validate_spool_path(&program.elf_file_path)?;
+            let image_id = match version {
+                #[cfg(feature = "risc0-v1")]
+                risc0_v1_verifier::VERSION => {
+                    risc0_v1_verifier::compute_image_id(&elf_file_path, envelope)?
+                }
+                _ => risc0_verifier::compute_image_id(&elf_file_path, envelope)?,
+            };
+            Ok((image_id, version))
+        });
+    match registered {
+        Ok((image_id, version)) => {
+            let image_id_str = risc0_verifier::format_image_id(&image_id);
+            info!(
+                "Registered RISC0 {} program {} with image ID {}",
+                version, program.program_id, image_id_str
+            );
+            let mut risc0_programs = risc0_programs.lock().await;
+            risc0_programs.insert(
+                program.program_id,
+                Risc0ProgramInfo {
+                    image_id,
+                    version: version.to_string(),
+                },
+            );
+            HttpResponse::Ok().json(RegistrationResult {
+                is_registered: true,
+                program_hash: Some(image_id_str),
+            })
+        }
+        Err(err) => {
+            warn!("Error registering RISC0 program: {:?}", err);
+            HttpResponse::Ok().json(RegistrationResult {
+                is_registered: false,
+                program_hash: None,
+            })
+        }
+    }
+}
+
+#[cfg(feature = "risc0")]
 #[post("/risc0-verify")]
 async fn verify_risc0(
     risc0_hashmap: web::Data<Arc<Mutex<HashMap<String, Risc0Proof>>>>,
+    risc0_programs: web::Data<Arc<Mutex<HashMap<String, Risc0ProgramInfo>>>>,
     data: web::Json<ProodDataRisc0>,
 ) -> impl Responder {
-    let mut risc0_hashmap = risc0_hashmap.lock().await;
     let proof_data = data.into_inner();
+    let proof_file_path = match validate_spool_path(&proof_data.proof_file_path) {
+        Ok(proof_file_path) => proof_file_path,
+        Err(err) => {
+            warn!("Rejecting RISC0 proof {}: {:?}", proof_data.tx_id, err);
+            return HttpResponse::Ok().json(SubmitionResult {
+                is_submitted: false,
+            });
+        }
+    };
+    let config = Config::init();
+    let risc0_programs = risc0_programs.lock().await;
+    let (image_id, program_version) = match risc0_verifier::resolve_image_id(
+        &risc0_programs,
+        proof_data.program_id.as_ref(),
+        proof_data.risc_zero_image_id.as_ref(),
+        config.risc0_registered_only,
+    ) {
+        Ok(resolved) => resolved,
+        Err(err) => {
+            warn!("Rejecting RISC0 proof {}: {:?}", proof_data.tx_id, err);
+            return HttpResponse::Ok().json(SubmitionResult {
+                is_submitted: false,
+            });
+        }
+    };
+    let selector = proof_data.version.as_deref().or(program_version.as_deref());
+    let version = match services::resolve_version(VERIFY_TYPE_RISC0, selector) {
+        Ok(version) => version,
+        Err(err) => {
+            warn!("Rejecting RISC0 proof {}: {:?}", proof_data.tx_id, err);
+            return HttpResponse::Ok().json(SubmitionResult {
+                is_submitted: false,
+            });
+        }
+    };
+    if let Some(program_version) =
+        program_version.filter(|program_version| program_version != version)
+    {
+        warn!(
+            "Rejecting RISC0 proof {}: program was registered for {}, proof selects {}",
+            proof_data.tx_id, program_version, version
+        );
+        return HttpResponse::Ok().json(SubmitionResult {
+            is_submitted: false,
+        });
+    }
+    let mut risc0_hashmap = risc0_hashmap.lock().await;
     risc0_hashmap.insert(
         proof_data.tx_id.clone(),
         Risc0Proof {
-            proof_file_path: proof_data.proof_file_path.clone(),
-            risc_zero_image_id: proof_data.risc_zero_image_id.clone(),
+            proof_file_path,
+            risc_zero_image_id: risc0_verifier::format_image_id(&image_id),
+            version: version.to_string(),
+            envelope: proof_data.envelope,
         },
     );
     HttpResponse::Ok().json(SubmitionResult { is_submitted: true })
@@ -119,74 +845,30 @@ async fn verify_risc0(
 #[post("/verify")]
 async fn verify(
     queue: web::Data<Arc<Mutex<VecDeque<VerifyProof>>>>,
-    sp1_hashmap: web::Data<Arc<Mutex<HashMap<String, Sp1Proof>>>>,
-    risc0_hashmap: web::Data<Arc<Mutex<HashMap<String, Risc0Proof>>>>,
-    miden_hashmap: web::Data<Arc<Mutex<HashMap<String, MidenProof>>>>,
-    jolt_hashmap: web::Data<Arc<Mutex<HashMap<String, JoltProof>>>>,
+    proof_stores: web::Data<ProofStores>,
     data: web::Json<VerifyProof>,
 ) -> impl Responder {
     info!("{:?}", data);
     let proof_data = data.into_inner();
+    if !services::is_compiled(proof_data.verify_type) {
+        warn!("Invalid proof type");
+        return HttpResponse::Ok().json(SubmitionResult {
+            is_submitted: false,
+        });
+    }
     let mut verify_queue = queue.lock().await;
-    match proof_data.verify_type {
-        1 => {
-            let sp1_hashmap = sp1_hashmap.lock().await;
-            match sp1_hashmap.get(&proof_data.tx_id) {
-                Some(_sp1_proof) => {
-                    verify_queue.push_back(proof_data);
-                }
-                None => {
-                    warn!("Invalid SP1 proof ID");
-                    return HttpResponse::Ok().json(SubmitionResult {
-                        is_submitted: false,
-                    });
-                }
-            }
-        }
-        2 => {
-            let miden_hashmap = miden_hashmap.lock().await;
-            match miden_hashmap.get(&proof_data.tx_id) {
-                Some(_miden_proof) => {
-                    verify_queue.push_back(proof_data);
-                }
-                None => {
-                    warn!("Invalid MIDEN proof ID");
-                    return HttpResponse::Ok().json(SubmitionResult {
-                        is_submitted: false,
-                    });
-                }
-            }
-        }
-        3 => {
-            let risc0_hashmap = risc0_hashmap.lock().await;
-            match risc0_hashmap.get(&proof_data.tx_id) {
-                Some(_risc0_proof) => {
-                    verify_queue.push_back(proof_data);
-                }
-                None => {
-                    warn!("Invalid RISC0 proof ID");
-                    return HttpResponse::Ok().json(SubmitionResult {
-                        is_submitted: false,
-                    });
-                }
-            }
-        }
-        4 => {
-            let jolt_hashmap = jolt_hashmap.lock().await;
-            match jolt_hashmap.get(&proof_data.tx_id) {
-                Some(_jolt_proof) => {
-                    verify_queue.push_back(proof_data);
-                }
-                None => {
-                    warn!("Invalid Jolt proof ID");
-                    return HttpResponse::Ok().json(SubmitionResult {
-                        is_submitted: false,
-                    });
-                }
-            }
+    match proof_stores
+        .job(proof_data.verify_type, &proof_data.tx_id)
+        .await
+    {
+        Some(_proof) => {
+            verify_queue.push_back(proof_data);
         }
-        _ => {
-            warn!("Invalid proof type");
+        None => {
+            warn!(
+                "Invalid {} proof ID",
+                backend_name(proof_data.verify_type).to_uppercase()
+            );
             return HttpResponse::Ok().json(SubmitionResult {
                 is_submitted: false,
             });
@@ -0,0 +1,203 @@
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
+
+use crate::config::Config;
+use crate::errors::VerificationError;
+use crate::models::{VerificationJob, VerificationResult};
+use crate::services;
+
+// passed to our own binary to make it verify a single job read from stdin
+pub const SANDBOX_ARG: &str = "--sandbox-verify";
+
+// the child writes its outcome here so whatever the verifiers print on stdout
+// cannot corrupt it
+const RESULT_FD: RawFd = 3;
+
+// tells the child which descriptor the parent set up for its outcome
+const RESULT_FD_ENV: &str = "JUGALBANDI_SANDBOX_RESULT_FD";
+
+// glibc types the resource as its own enum, musl and the BSDs take a c_int
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type RlimitResource = libc::__rlimit_resource_t;
+#[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+type RlimitResource = libc::c_int;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub enum SandboxOutcome {
+    Verified(VerificationResult),
+    Failed(Vec<String>),
+}
+
+pub fn is_sandbox_child() -> bool {
+    std::env::args().any(|arg| arg == SANDBOX_ARG)
+}
+
+// entry point of the child process, the job comes in on stdin and the
+// outcome goes out on RESULT_FD, logs go to stderr
+pub async fn run_child() -> std::io::Result<()> {
+    let mut input = Vec::new();
+    std::io::stdin().read_to_end(&mut input)?;
+    let job: VerificationJob = serde_json::from_slice(&input)?;
+    let outcome = match services::verify(job).await {
+        Ok(result) => SandboxOutcome::Verified(result),
+        Err(err) => SandboxOutcome::Failed(err.chain()),
+    };
+    let mut result = result_file()?;
+    result.write_all(&serde_json::to_vec(&outcome)?)?;
+    result.flush()
+}
+
+// the descriptor named by the parent, refusing anything that isn't an open
+// pipe so the outcome never lands in a file or socket we happened to inherit
+fn result_file() -> std::io::Result<File> {
+    let invalid = |message: String| std::io::Error::new(std::io::ErrorKind::InvalidInput, message);
+    let fd: RawFd = std::env::var(RESULT_FD_ENV)
+        .map_err(|_| invalid(format!("{RESULT_FD_ENV} is not set")))?
+        .parse()
+        .map_err(|err| invalid(format!("{RESULT_FD_ENV} is not a descriptor: {err}")))?;
+    if fd <= libc::STDERR_FILENO {
+        return Err(invalid(format!(
+            "{RESULT_FD_ENV} names standard descriptor {fd}"
+        )));
+    }
+    if unsafe { libc::fcntl(fd, libc::F_GETFD) } == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let mut stat = std::mem::MaybeUninit::<libc::stat>::uninit();
+    if unsafe { libc::fstat(fd, stat.as_mut_ptr()) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    if unsafe { stat.assume_init() }.st_mode & libc::S_IFMT != libc::S_IFIFO {
+        return Err(invalid(format!("descriptor {fd} is not a pipe")));
+    }
+    // checked open above, and nothing else in the child owns it
+    Ok(unsafe { File::from_raw_fd(fd) })
+}
+
+// both ends are closed on exec, the child dups the write end onto RESULT_FD
+fn result_pipe() -> std::io::Result<(OwnedFd, OwnedFd)> {
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let pipe = unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) };
+    for fd in fds {
+        if unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(pipe)
+}
+
+fn set_limit(resource: RlimitResource, limit: u64) -> std::io::Result<()> {
+    let rlimit = libc::rlimit {
+        rlim_cur: limit as libc::rlim_t,
+        rlim_max: limit as libc::rlim_t,
+    };
+    if unsafe { libc::setrlimit(resource, &rlimit) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+pub async fn verify_sandboxed(
+    job: VerificationJob,
+) -> Result<VerificationResult, VerificationError> {
+    let config = Config::init();
+    let payload = serde_json::to_vec(&job)
+        .map_err(|err| VerificationError::JSONError(err, "Error encoding job".to_string()))?;
+    let executable = std::env::current_exe().map_err(|err| {
+        VerificationError::IOError(err, "Error locating verifier executable".to_string())
+    })?;
+
+    let (result_read, result_write) = result_pipe().map_err(|err| {
+        VerificationError::IOError(err, "Error creating sandbox result pipe".to_string())
+    })?;
+    let result_write_fd = result_write.as_raw_fd();
+    let cpu_secs = config.sandbox_cpu_secs;
+    let memory_bytes = config.sandbox_memory_bytes;
+    let mut command = Command::new(executable);
+    command
+        .arg(SANDBOX_ARG)
+        .env(RESULT_FD_ENV, RESULT_FD.to_string())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .kill_on_drop(true);
+    // runs in the forked child right before exec
+    unsafe {
+        command.pre_exec(move || {
+            if cpu_secs > 0 {
+                set_limit(libc::RLIMIT_CPU, cpu_secs)?;
+            }
+            if memory_bytes > 0 {
+                set_limit(libc::RLIMIT_AS, memory_bytes)?;
+            }
+            // dup2 clears close on exec, unless the pipe already sits on RESULT_FD
+            let dup_result = if result_write_fd == RESULT_FD {
+                libc::fcntl(RESULT_FD, libc::F_SETFD, 0)
+            } else {
+                libc::dup2(result_write_fd, RESULT_FD)
+            };
+            if dup_result < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+
+    let mut child = command.spawn().map_err(|err| {
+        VerificationError::IOError(err, "Error spawning sandbox process".to_string())
+    })?;
+    // only the child may hold the write end, or reading never sees EOF
+    drop(result_write);
+    let mut result_reader = tokio::fs::File::from_std(File::from(result_read));
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(&payload).await.map_err(|err| {
+            VerificationError::IOError(err, "Error sending job to sandbox".to_string())
+        })?;
+    }
+
+    // dropping the child on timeout kills it
+    let timeout = Duration::from_secs(config.sandbox_timeout_secs);
+    let run = async {
+        let mut result = Vec::new();
+        result_reader.read_to_end(&mut result).await?;
+        let status = child.wait().await?;
+        Ok::<_, std::io::Error>((status, result))
+    };
+    let (status, result) = match tokio::time::timeout(timeout, run).await {
+        Ok(output) => output.map_err(|err| {
+            VerificationError::IOError(err, "Error waiting for sandbox process".to_string())
+        })?,
+        Err(_) => {
+            warn!("Sandboxed verification timed out after {:?}", timeout);
+            return Err(VerificationError::Sandbox(format!(
+                "timed out after {} seconds",
+                config.sandbox_timeout_secs
+            )));
+        }
+    };
+    if !status.success() {
+        return Err(VerificationError::Sandbox(format!(
+            "verifier process exited with {}",
+            status
+        )));
+    }
+    info!("Sandboxed verification finished with {}", status);
+
+    let outcome: SandboxOutcome = serde_json::from_slice(&result).map_err(|err| {
+        VerificationError::JSONError(err, "Error parsing sandbox outcome".to_string())
+    })?;
+    match outcome {
+        SandboxOutcome::Verified(result) => Ok(result),
+        SandboxOutcome::Failed(chain) => Err(VerificationError::Sandbox(chain.join(": "))),
+    }
+}
@@ -0,0 +1,140 @@
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::str::FromStr;
+
+use crate::errors::VerificationError;
+
+pub const HYPERSDK_PREFIX_LEN: usize = 32;
+
+const ELF_MAGIC: &[u8] = b"\x7fELF";
+
+// framing the files handed to us are wrapped in
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Envelope {
+    // the file is the payload
+    Raw,
+    // hypersdk writes the 32 byte id of the object in front of the payload
+    HyperSdk,
+}
+
+impl FromStr for Envelope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "raw" => Ok(Envelope::Raw),
+            "hypersdk" => Ok(Envelope::HyperSdk),
+            _ => Err(format!("Unknown envelope format {s}")),
+        }
+    }
+}
+
+impl Envelope {
+    pub fn unwrap_bytes(&self, mut bytes: Vec<u8>) -> Result<Vec<u8>, VerificationError> {
+        match self {
+            Envelope::Raw => Ok(bytes),
+            Envelope::HyperSdk => {
+                if bytes.len() < HYPERSDK_PREFIX_LEN {
+                    return Err(VerificationError::EnvelopeError(format!(
+                        "expected a {} byte hypersdk prefix, file has {} bytes",
+                        HYPERSDK_PREFIX_LEN,
+                        bytes.len()
+                    )));
+                }
+                let mut prefix = [0u8; HYPERSDK_PREFIX_LEN];
+                prefix.copy_from_slice(&bytes[..HYPERSDK_PREFIX_LEN]);
+                if prefix.starts_with(ELF_MAGIC) {
+                    return Err(VerificationError::EnvelopeError(
+                        "file starts with an ELF header, it is not hypersdk enveloped".to_string(),
+                    ));
+                }
+                if prefix.iter().all(|byte| *byte == 0) {
+                    return Err(VerificationError::EnvelopeError(
+                        "hypersdk prefix is the empty id".to_string(),
+                    ));
+                }
+                info!("Unwrapped hypersdk envelope {}", hex::encode(prefix));
+                Ok(bytes.split_off(HYPERSDK_PREFIX_LEN))
+            }
+        }
+    }
+
+    pub fn read_file(&self, file_path: &str) -> Result<Vec<u8>, VerificationError> {
+        let bytes = fs::read(file_path).map_err(|err| {
+            VerificationError::IOError(err, format!("Error reading file {file_path}"))
+        })?;
+        self.unwrap_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enveloped(id: [u8; HYPERSDK_PREFIX_LEN], payload: &[u8]) -> Vec<u8> {
+        [id.as_slice(), payload].concat()
+    }
+
+    #[test]
+    fn raw_keeps_every_byte() {
+        for bytes in [vec![], vec![0u8; 64], b"\x7fELF\x02\x01".to_vec()] {
+            assert_eq!(Envelope::Raw.unwrap_bytes(bytes.clone()).unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn hypersdk_strips_the_id() {
+        let payload = b"{\"proof\": [1, 2, 3]}";
+        let bytes = enveloped([7u8; HYPERSDK_PREFIX_LEN], payload);
+        assert_eq!(Envelope::HyperSdk.unwrap_bytes(bytes).unwrap(), payload);
+        let bytes = enveloped([7u8; HYPERSDK_PREFIX_LEN], b"");
+        assert!(Envelope::HyperSdk.unwrap_bytes(bytes).unwrap().is_empty());
+    }
+
+    // anything that doesn't look like a bare ELF or an empty id loses its
+    // first 32 bytes, whether or not they were an id
+    #[test]
+    fn hypersdk_strips_any_other_prefix() {
+        let bytes = b"{\"pi_a\": [\"1\", \"2\", \"1\"], \"pi_b\": []}".to_vec();
+        assert_eq!(
+            Envelope::HyperSdk.unwrap_bytes(bytes.clone()).unwrap(),
+            &bytes[HYPERSDK_PREFIX_LEN..]
+        );
+    }
+
+    #[test]
+    fn hypersdk_rejects_short_files() {
+        for len in [0, 1, HYPERSDK_PREFIX_LEN - 1] {
+            let err = Envelope::HyperSdk.unwrap_bytes(vec![7u8; len]).unwrap_err();
+            assert!(matches!(err, VerificationError::EnvelopeError(_)));
+        }
+    }
+
+    #[test]
+    fn hypersdk_rejects_elf_files() {
+        let mut bytes = b"\x7fELF\x02\x01\x01".to_vec();
+        bytes.resize(256, 0);
+        let err = Envelope::HyperSdk.unwrap_bytes(bytes).unwrap_err();
+        assert!(matches!(err, VerificationError::EnvelopeError(_)));
+    }
+
+    #[test]
+    fn hypersdk_rejects_the_empty_id() {
+        let bytes = enveloped([0u8; HYPERSDK_PREFIX_LEN], b"payload");
+        let err = Envelope::HyperSdk.unwrap_bytes(bytes).unwrap_err();
+        assert!(matches!(err, VerificationError::EnvelopeError(_)));
+        let err = Envelope::HyperSdk
+            .unwrap_bytes(vec![0u8; HYPERSDK_PREFIX_LEN])
+            .unwrap_err();
+        assert!(matches!(err, VerificationError::EnvelopeError(_)));
+    }
+
+    #[test]
+    fn parses_envelope_names() {
+        assert_eq!("raw".parse::<Envelope>().unwrap(), Envelope::Raw);
+        assert_eq!("HyperSDK".parse::<Envelope>().unwrap(), Envelope::HyperSdk);
+        assert!("hyper".parse::<Envelope>().is_err());
+    }
+}
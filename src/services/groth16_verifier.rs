@@ -0,0 +1,587 @@
+use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ec::short_weierstrass::SWCurveConfig;
+use ark_ff::{Field, PrimeField, Zero};
+use ark_groth16::{prepare_verifying_key, Groth16, Proof, VerifyingKey};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use log::{info, warn};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::str::FromStr;
+
+use crate::config::{Config, VERIFY_TYPE_GROTH16};
+use crate::errors::VerificationError;
+use crate::models::{Groth16Key, Groth16KeyInfo, Groth16Proof, VerificationResult};
+
+pub const VERSION: &str = env!("ARK_GROTH16_VERSION");
+
+// encodings of the verifying key and proof files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Groth16Format {
+    // verification_key.json and proof.json as written by snarkjs
+    Snarkjs,
+    // VerifyingKey and Proof as written by gnark, compressed or raw
+    Gnark,
+}
+
+impl FromStr for Groth16Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "snarkjs" => Ok(Groth16Format::Snarkjs),
+            "gnark" => Ok(Groth16Format::Gnark),
+            _ => Err(format!("Unknown Groth16 format {s}")),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SnarkjsVerifyingKey {
+    protocol: Option<String>,
+    curve: Option<String>,
+    vk_alpha_1: Vec<String>,
+    vk_beta_2: Vec<Vec<String>>,
+    vk_gamma_2: Vec<Vec<String>>,
+    vk_delta_2: Vec<Vec<String>>,
+    #[serde(rename = "IC")]
+    ic: Vec<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+struct SnarkjsProof {
+    protocol: Option<String>,
+    curve: Option<String>,
+    pi_a: Vec<String>,
+    pi_b: Vec<Vec<String>>,
+    pi_c: Vec<String>,
+}
+
+// decimal as used by snarkjs, or 0x prefixed hex, rejecting values that are
+// not reduced modulo the field
+fn parse_field<F: PrimeField>(value: &str) -> Result<F, String> {
+    let value = value.trim();
+    let decimal = match value.strip_prefix("0x") {
+        Some(hex_value) => {
+            if hex_value.len() > 64 {
+                return Err(format!("{value} is longer than 32 bytes"));
+            }
+            let mut bytes = hex::decode(format!("{:0>64}", hex_value))
+                .map_err(|err| format!("{value} is not hex: {err}"))?;
+            bytes.reverse();
+            return F::deserialize_compressed(&bytes[..])
+                .map_err(|err| format!("{value} is not a field element: {err}"));
+        }
+        None => value.trim_start_matches('0'),
+    };
+    let decimal = if decimal.is_empty() { "0" } else { decimal };
+    if !decimal.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("{value} is not a decimal number"));
+    }
+    let element: F = decimal
+        .parse()
+        .map_err(|_| format!("{value} is not a field element"))?;
+    if element.into_bigint().to_string() != decimal {
+        return Err(format!("{value} is not reduced modulo the field"));
+    }
+    Ok(element)
+}
+
+fn checked_g1(point: G1Affine) -> Result<G1Affine, String> {
+    if !point.is_on_curve() {
+        return Err("G1 point is not on the curve".to_string());
+    }
+    Ok(point)
+}
+
+fn checked_g2(point: G2Affine) -> Result<G2Affine, String> {
+    if !point.is_on_curve() {
+        return Err("G2 point is not on the curve".to_string());
+    }
+    if !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err("G2 point is not in the prime order subgroup".to_string());
+    }
+    Ok(point)
+}
+
+// snarkjs writes projective coordinates, which are normalized to z = 1
+fn snarkjs_g1(point: &[String]) -> Result<G1Affine, String> {
+    match point {
+        [x, y, z] if z.trim() == "1" => {
+            checked_g1(G1Affine::new_unchecked(parse_field(x)?, parse_field(y)?))
+        }
+        _ => Err(format!(
+            "expected an affine G1 point [x, y, \"1\"], got {point:?}"
+        )),
+    }
+}
+
+fn snarkjs_fq2(element: &[String]) -> Result<Fq2, String> {
+    match element {
+        [c0, c1] => Ok(Fq2::new(parse_field(c0)?, parse_field(c1)?)),
+        _ => Err(format!("expected an Fq2 element [c0, c1], got {element:?}")),
+    }
+}
+
+fn snarkjs_g2(point: &[Vec<String>]) -> Result<G2Affine, String> {
+    match point {
+        [x, y, z] => {
+            if snarkjs_fq2(z)? != Fq2::ONE {
+                return Err(format!("expected an affine G2 point, got z = {z:?}"));
+            }
+            checked_g2(G2Affine::new_unchecked(snarkjs_fq2(x)?, snarkjs_fq2(y)?))
+        }
+        _ => Err(format!("expected a G2 point [x, y, z], got {point:?}")),
+    }
+}
+
+fn check_header(protocol: &Option<String>, curve: &Option<String>) -> Result<(), String> {
+    if let Some(protocol) = protocol {
+        if protocol != "groth16" {
+            return Err(format!("protocol is {protocol}, expected groth16"));
+        }
+    }
+    if let Some(curve) = curve {
+        if curve != "bn128" && curve != "bn254" {
+            return Err(format!("curve is {curve}, expected bn128"));
+        }
+    }
+    Ok(())
+}
+
+fn snarkjs_verifying_key(bytes: &[u8]) -> Result<VerifyingKey<Bn254>, String> {
+    let vk: SnarkjsVerifyingKey = serde_json::from_slice(bytes).map_err(|err| err.to_string())?;
+    check_header(&vk.protocol, &vk.curve)?;
+    Ok(VerifyingKey {
+        alpha_g1: snarkjs_g1(&vk.vk_alpha_1)?,
+        beta_g2: snarkjs_g2(&vk.vk_beta_2)?,
+        gamma_g2: snarkjs_g2(&vk.vk_gamma_2)?,
+        delta_g2: snarkjs_g2(&vk.vk_delta_2)?,
+        gamma_abc_g1: vk
+            .ic
+            .iter()
+            .map(|point| snarkjs_g1(point))
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+fn snarkjs_proof(bytes: &[u8]) -> Result<Proof<Bn254>, String> {
+    let proof: SnarkjsProof = serde_json::from_slice(bytes).map_err(|err| err.to_string())?;
+    check_header(&proof.protocol, &proof.curve)?;
+    Ok(Proof {
+        a: snarkjs_g1(&proof.pi_a)?,
+        b: snarkjs_g2(&proof.pi_b)?,
+        c: snarkjs_g1(&proof.pi_c)?,
+    })
+}
+
+// flags gnark keeps in the two most significant bits of a point encoding, the
+// remaining 0b10 marks a compressed point with the smaller of the two ys
+const GNARK_MASK: u8 = 0b11 << 6;
+const GNARK_UNCOMPRESSED: u8 = 0b00 << 6;
+const GNARK_COMPRESSED_LARGEST: u8 = 0b11 << 6;
+const GNARK_COMPRESSED_INFINITY: u8 = 0b01 << 6;
+
+// gnark's notion of an element being larger than its negation
+fn lexicographically_largest_fq(element: &Fq) -> bool {
+    element.into_bigint() > Fq::MODULUS_MINUS_ONE_DIV_TWO
+}
+
+fn lexicographically_largest_fq2(element: &Fq2) -> bool {
+    if element.c0.is_zero() {
+        lexicographically_largest_fq(&element.c1)
+    } else {
+        lexicographically_largest_fq(&element.c0)
+    }
+}
+
+// reads gnark encoded points one after the other, each point carries its own
+// compression flag so raw and compressed files are read the same way
+struct GnarkReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> GnarkReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.bytes.len() < len {
+            return Err(format!(
+                "unexpected end of data, needed {} bytes, {} left",
+                len,
+                self.bytes.len()
+            ));
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, String> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn fq(bytes: &[u8]) -> Result<Fq, String> {
+        let mut little_endian = bytes.to_vec();
+        little_endian.reverse();
+        Fq::deserialize_compressed(&little_endian[..])
+            .map_err(|err| format!("coordinate is not a field element: {err}"))
+    }
+
+    fn flags(&self) -> Result<u8, String> {
+        self.bytes
+            .first()
+            .map(|byte| byte & GNARK_MASK)
+            .ok_or_else(|| "unexpected end of data".to_string())
+    }
+
+    // the first coordinate with the flag bits cleared
+    fn coordinate(&mut self, len: usize) -> Result<Vec<u8>, String> {
+        let mut bytes = self.take(len)?.to_vec();
+        bytes[0] &= !GNARK_MASK;
+        Ok(bytes)
+    }
+
+    fn g1(&mut self) -> Result<G1Affine, String> {
+        let flags = self.flags()?;
+        if flags == GNARK_COMPRESSED_INFINITY {
+            self.take(32)?;
+            return Ok(G1Affine::identity());
+        }
+        if flags == GNARK_UNCOMPRESSED {
+            let x = self.coordinate(32)?;
+            let y = self.take(32)?;
+            if x.iter().chain(y).all(|byte| *byte == 0) {
+                return Ok(G1Affine::identity());
+            }
+            return checked_g1(G1Affine::new_unchecked(Self::fq(&x)?, Self::fq(y)?));
+        }
+        let x = Self::fq(&self.coordinate(32)?)?;
+        let y_squared = x * x * x + Fq::from(3u64);
+        let y = y_squared
+            .sqrt()
+            .ok_or_else(|| "compressed G1 point is not on the curve".to_string())?;
+        let y = if lexicographically_largest_fq(&y) == (flags == GNARK_COMPRESSED_LARGEST) {
+            y
+        } else {
+            -y
+        };
+        checked_g1(G1Affine::new_unchecked(x, y))
+    }
+
+    // gnark writes the imaginary part of an Fq2 element first
+    fn fq2(bytes: &[u8]) -> Result<Fq2, String> {
+        Ok(Fq2::new(Self::fq(&bytes[32..64])?, Self::fq(&bytes[..32])?))
+    }
+
+    fn g2(&mut self) -> Result<G2Affine, String> {
+        let flags = self.flags()?;
+        if flags == GNARK_COMPRESSED_INFINITY {
+            self.take(64)?;
+            return Ok(G2Affine::identity());
+        }
+        if flags == GNARK_UNCOMPRESSED {
+            let x = self.coordinate(64)?;
+            let y = self.take(64)?;
+            if x.iter().chain(y).all(|byte| *byte == 0) {
+                return Ok(G2Affine::identity());
+            }
+            return checked_g2(G2Affine::new_unchecked(Self::fq2(&x)?, Self::fq2(y)?));
+        }
+        let x = Self::fq2(&self.coordinate(64)?)?;
+        let y_squared = x * x * x + ark_bn254::g2::Config::COEFF_B;
+        let y = y_squared
+            .sqrt()
+            .ok_or_else(|| "compressed G2 point is not on the curve".to_string())?;
+        let y = if lexicographically_largest_fq2(&y) == (flags == GNARK_COMPRESSED_LARGEST) {
+            y
+        } else {
+            -y
+        };
+        checked_g2(G2Affine::new_unchecked(x, y))
+    }
+
+    // newer gnark versions append Pedersen commitment data, which this
+    // verifier does not support, older versions end right here
+    fn expect_no_commitments(&mut self) -> Result<(), String> {
+        if self.bytes.is_empty() {
+            return Ok(());
+        }
+        match self.read_u32()? {
+            0 => Ok(()),
+            count => Err(format!("{count} Pedersen commitments are not supported")),
+        }
+    }
+}
+
+fn gnark_verifying_key(bytes: &[u8]) -> Result<VerifyingKey<Bn254>, String> {
+    let mut reader = GnarkReader { bytes };
+    let alpha_g1 = reader.g1()?;
+    let _beta_g1 = reader.g1()?;
+    let beta_g2 = reader.g2()?;
+    let gamma_g2 = reader.g2()?;
+    let _delta_g1 = reader.g1()?;
+    let delta_g2 = reader.g2()?;
+    let k_len = reader.read_u32()?;
+    let gamma_abc_g1 = (0..k_len).map(|_| reader.g1()).collect::<Result<_, _>>()?;
+    reader.expect_no_commitments()?;
+    Ok(VerifyingKey {
+        alpha_g1,
+        beta_g2,
+        gamma_g2,
+        delta_g2,
+        gamma_abc_g1,
+    })
+}
+
+fn gnark_proof(bytes: &[u8]) -> Result<Proof<Bn254>, String> {
+    let mut reader = GnarkReader { bytes };
+    let a = reader.g1()?;
+    let b = reader.g2()?;
+    let c = reader.g1()?;
+    reader.expect_no_commitments()?;
+    Ok(Proof { a, b, c })
+}
+
+fn parse_format(format: Option<&str>) -> Result<Groth16Format, VerificationError> {
+    format
+        .unwrap_or("snarkjs")
+        .parse()
+        .map_err(VerificationError::ProofError)
+}
+
+pub fn read_verifying_key(
+    format: Groth16Format,
+    bytes: &[u8],
+) -> Result<VerifyingKey<Bn254>, VerificationError> {
+    match format {
+        Groth16Format::Snarkjs => snarkjs_verifying_key(bytes),
+        Groth16Format::Gnark => gnark_verifying_key(bytes),
+    }
+    .map_err(|err| {
+        VerificationError::KeyError(format!("Error reading {:?} verifying key: {err}", format))
+    })
+}
+
+pub fn read_proof(format: Groth16Format, bytes: &[u8]) -> Result<Proof<Bn254>, VerificationError> {
+    match format {
+        Groth16Format::Snarkjs => snarkjs_proof(bytes),
+        Groth16Format::Gnark => gnark_proof(bytes),
+    }
+    .map_err(|err| {
+        VerificationError::ProofError(format!("Error reading {:?} proof: {err}", format))
+    })
+}
+
+// the key is kept in arkworks' compressed encoding so queued jobs carry it
+// into the sandbox, its sha256 pins the circuit in attestations
+pub fn register_key(key: &Groth16Key) -> Result<Groth16KeyInfo, VerificationError> {
+    let format = parse_format(key.format.as_deref())?;
+    let envelope = key
+        .envelope
+        .unwrap_or(Config::init().envelope_for(VERIFY_TYPE_GROTH16));
+    let vk = read_verifying_key(format, &envelope.read_file(&key.vk_file_path)?)?;
+    let mut vk_bytes = Vec::new();
+    vk.serialize_compressed(&mut vk_bytes).map_err(|err| {
+        VerificationError::KeyError(format!("Error encoding verifying key: {err}"))
+    })?;
+    Ok(Groth16KeyInfo {
+        vk_hash: hex::encode(Sha256::digest(&vk_bytes)),
+        vk: hex::encode(vk_bytes),
+    })
+}
+
+fn registered_verifying_key(vk: &str) -> Result<VerifyingKey<Bn254>, VerificationError> {
+    let vk_bytes = hex::decode(vk).map_err(|err| {
+        VerificationError::KeyError(format!("Registered verifying key is not hex: {err}"))
+    })?;
+    VerifyingKey::deserialize_compressed(&vk_bytes[..]).map_err(|err| {
+        VerificationError::KeyError(format!("Error decoding registered verifying key: {err}"))
+    })
+}
+
+pub fn verify_proof(
+    vk: &VerifyingKey<Bn254>,
+    proof: &Proof<Bn254>,
+    public_inputs: &[String],
+) -> Result<bool, VerificationError> {
+    let public_inputs = public_inputs
+        .iter()
+        .enumerate()
+        .map(|(index, input)| {
+            parse_field::<Fr>(input).map_err(|err| {
+                VerificationError::ParseError(format!("public input {index}: {err}"))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if public_inputs.len() + 1 != vk.gamma_abc_g1.len() {
+        return Err(VerificationError::ParseError(format!(
+            "verifying key expects {} public inputs, got {}",
+            vk.gamma_abc_g1.len().saturating_sub(1),
+            public_inputs.len()
+        )));
+    }
+
+    let pvk = prepare_verifying_key(vk);
+    match Groth16::<Bn254>::verify_proof(&pvk, proof, &public_inputs) {
+        Ok(is_valid) => Ok(is_valid),
+        Err(err) => {
+            warn!("Verification failed : {:?}", err);
+            Ok(false)
+        }
+    }
+}
+
+pub async fn verify(data: &Groth16Proof) -> Result<VerificationResult, VerificationError> {
+    info!("{:?}", data);
+    let envelope = data
+        .envelope
+        .unwrap_or(Config::init().envelope_for(VERIFY_TYPE_GROTH16));
+    let format = parse_format(data.format.as_deref())?;
+    let vk = registered_verifying_key(&data.vk)?;
+    let proof = read_proof(format, &envelope.read_file(&data.proof_file_path)?)?;
+    let is_valid = verify_proof(&vk, &proof, &data.public_inputs)?;
+    Ok(VerificationResult {
+        is_valid,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SNARKJS_VK: &[u8] = include_bytes!("../../tests/fixtures/groth16/snarkjs_vk.json");
+    const SNARKJS_PROOF: &[u8] = include_bytes!("../../tests/fixtures/groth16/snarkjs_proof.json");
+    const GNARK_VK: &[u8] = include_bytes!("../../tests/fixtures/groth16/gnark_vk.bin");
+    const GNARK_PROOF: &[u8] = include_bytes!("../../tests/fixtures/groth16/gnark_proof.bin");
+    const PUBLIC_INPUTS: &[u8] = include_bytes!("../../tests/fixtures/groth16/public.json");
+
+    fn public_inputs() -> Vec<String> {
+        serde_json::from_slice(PUBLIC_INPUTS).unwrap()
+    }
+
+    #[test]
+    fn verifies_snarkjs_fixture() {
+        let vk = read_verifying_key(Groth16Format::Snarkjs, SNARKJS_VK).unwrap();
+        let proof = read_proof(Groth16Format::Snarkjs, SNARKJS_PROOF).unwrap();
+        assert!(verify_proof(&vk, &proof, &public_inputs()).unwrap());
+    }
+
+    #[test]
+    fn verifies_gnark_fixture() {
+        let vk = read_verifying_key(Groth16Format::Gnark, GNARK_VK).unwrap();
+        let proof = read_proof(Groth16Format::Gnark, GNARK_PROOF).unwrap();
+        assert!(verify_proof(&vk, &proof, &public_inputs()).unwrap());
+    }
+
+    #[test]
+    fn gnark_and_snarkjs_encode_the_same_key() {
+        let snarkjs = read_verifying_key(Groth16Format::Snarkjs, SNARKJS_VK).unwrap();
+        let gnark = read_verifying_key(Groth16Format::Gnark, GNARK_VK).unwrap();
+        assert_eq!(snarkjs, gnark);
+        let snarkjs = read_proof(Groth16Format::Snarkjs, SNARKJS_PROOF).unwrap();
+        let gnark = read_proof(Groth16Format::Gnark, GNARK_PROOF).unwrap();
+        assert_eq!(snarkjs, gnark);
+    }
+
+    #[test]
+    fn registered_key_round_trips() {
+        let vk = read_verifying_key(Groth16Format::Gnark, GNARK_VK).unwrap();
+        let mut vk_bytes = Vec::new();
+        vk.serialize_compressed(&mut vk_bytes).unwrap();
+        assert_eq!(
+            registered_verifying_key(&hex::encode(vk_bytes)).unwrap(),
+            vk
+        );
+    }
+
+    // with no ENVELOPE set the files are read as snarkjs and gnark wrote them
+    #[actix_web::test]
+    async fn registers_and_verifies_with_the_default_envelope() {
+        let dir = tempfile::tempdir().unwrap();
+        for (format, vk_file, proof_file) in [
+            ("snarkjs", SNARKJS_VK, SNARKJS_PROOF),
+            ("gnark", GNARK_VK, GNARK_PROOF),
+        ] {
+            let vk_file_path = dir.path().join(format!("{format}_vk"));
+            let proof_file_path = dir.path().join(format!("{format}_proof"));
+            std::fs::write(&vk_file_path, vk_file).unwrap();
+            std::fs::write(&proof_file_path, proof_file).unwrap();
+            let key_info = register_key(&Groth16Key {
+                key_id: format.to_string(),
+                vk_file_path: vk_file_path.to_string_lossy().to_string(),
+                format: Some(format.to_string()),
+                envelope: None,
+            })
+            .unwrap();
+            let verification_result = verify(&Groth16Proof {
+                vk: key_info.vk,
+                vk_hash: key_info.vk_hash,
+                proof_file_path: proof_file_path.to_string_lossy().to_string(),
+                public_inputs: public_inputs(),
+                format: Some(format.to_string()),
+                envelope: None,
+            })
+            .await
+            .unwrap();
+            assert!(verification_result.is_valid, "{format}");
+        }
+    }
+
+    #[test]
+    fn rejects_wrong_public_inputs() {
+        let vk = read_verifying_key(Groth16Format::Snarkjs, SNARKJS_VK).unwrap();
+        let proof = read_proof(Groth16Format::Snarkjs, SNARKJS_PROOF).unwrap();
+        let inputs = vec!["33".to_string(), "4".to_string()];
+        assert!(!verify_proof(&vk, &proof, &inputs).unwrap());
+        assert!(verify_proof(&vk, &proof, &inputs[..1]).is_err());
+    }
+
+    #[test]
+    fn rejects_unreduced_public_inputs() {
+        let vk = read_verifying_key(Groth16Format::Snarkjs, SNARKJS_VK).unwrap();
+        let proof = read_proof(Groth16Format::Snarkjs, SNARKJS_PROOF).unwrap();
+        let modulus = Fr::MODULUS.to_string();
+        let inputs = vec![modulus, "3".to_string()];
+        assert!(verify_proof(&vk, &proof, &inputs).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_gnark_files() {
+        for len in [0, 31, 32, GNARK_PROOF.len() - 1] {
+            assert!(read_proof(Groth16Format::Gnark, &GNARK_PROOF[..len]).is_err());
+        }
+        for len in [0, 64, GNARK_VK.len() - 32, GNARK_VK.len() - 1] {
+            assert!(read_verifying_key(Groth16Format::Gnark, &GNARK_VK[..len]).is_err());
+        }
+    }
+
+    #[test]
+    fn rejects_gnark_commitments() {
+        let mut proof = GNARK_PROOF.to_vec();
+        proof.extend_from_slice(&1u32.to_be_bytes());
+        assert!(read_proof(Groth16Format::Gnark, &proof).is_err());
+    }
+
+    #[test]
+    fn rejects_points_off_the_curve() {
+        let mut proof = GNARK_PROOF.to_vec();
+        proof[63] ^= 1;
+        assert!(read_proof(Groth16Format::Gnark, &proof).is_err());
+
+        let mut proof: serde_json::Value = serde_json::from_slice(SNARKJS_PROOF).unwrap();
+        proof["pi_a"][1] = "1".into();
+        let proof = serde_json::to_vec(&proof).unwrap();
+        assert!(read_proof(Groth16Format::Snarkjs, &proof).is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_snarkjs_files() {
+        assert!(read_verifying_key(Groth16Format::Snarkjs, b"{}").is_err());
+        assert!(read_proof(Groth16Format::Snarkjs, &SNARKJS_PROOF[..100]).is_err());
+
+        let mut vk: serde_json::Value = serde_json::from_slice(SNARKJS_VK).unwrap();
+        vk["curve"] = "bls12381".into();
+        let vk = serde_json::to_vec(&vk).unwrap();
+        assert!(read_verifying_key(Groth16Format::Snarkjs, &vk).is_err());
+    }
+}
@@ -0,0 +1,66 @@
+use halo2_proofs::arithmetic::Field;
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance, Selector};
+use halo2_proofs::poly::Rotation;
+
+#[derive(Debug, Clone)]
+pub struct SquareConfig {
+    advice: Column<Advice>,
+    instance: Column<Instance>,
+    selector: Selector,
+}
+
+// knowledge of a root of the single public instance value: the root sits in
+// the first advice row, its square in the next, and the square is tied to
+// instance row 0. Small enough to serve as the reference circuit for each
+// commitment scheme
+#[derive(Debug, Clone)]
+pub struct SquareCircuit<F: Field> {
+    pub root: Value<F>,
+}
+
+impl<F: Field> Circuit<F> for SquareCircuit<F> {
+    type Config = SquareConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        SquareCircuit {
+            root: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> SquareConfig {
+        let advice = meta.advice_column();
+        let instance = meta.instance_column();
+        let selector = meta.selector();
+        meta.enable_equality(advice);
+        meta.enable_equality(instance);
+        meta.create_gate("square", |meta| {
+            let selector = meta.query_selector(selector);
+            let root = meta.query_advice(advice, Rotation::cur());
+            let square = meta.query_advice(advice, Rotation::next());
+            vec![selector * (root.clone() * root - square)]
+        });
+        SquareConfig {
+            advice,
+            instance,
+            selector,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: SquareConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let square = layouter.assign_region(
+            || "square",
+            |mut region| {
+                config.selector.enable(&mut region, 0)?;
+                region.assign_advice(|| "root", config.advice, 0, || self.root)?;
+                region.assign_advice(|| "square", config.advice, 1, || self.root * self.root)
+            },
+        )?;
+        layouter.constrain_instance(square.cell(), config.instance, 0)
+    }
+}
@@ -0,0 +1,489 @@
+use halo2_proofs::halo2curves::bn256::{Bn256, Fr, G1Affine};
+use halo2_proofs::halo2curves::ff::PrimeField;
+use halo2_proofs::halo2curves::pasta::{EqAffine, Fp};
+use halo2_proofs::plonk::{verify_proof, Circuit, VerifyingKey};
+use halo2_proofs::poly::commitment::Params;
+use halo2_proofs::poly::ipa::commitment::{IPACommitmentScheme, ParamsIPA};
+use halo2_proofs::poly::ipa::multiopen::VerifierIPA;
+use halo2_proofs::poly::ipa::strategy::SingleStrategy as IpaSingleStrategy;
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::{VerifierGWC, VerifierSHPLONK};
+use halo2_proofs::poly::kzg::strategy::SingleStrategy as KzgSingleStrategy;
+use halo2_proofs::transcript::{Blake2bRead, Challenge255, TranscriptReadBuffer};
+use halo2_proofs::SerdeFormat;
+use log::{info, warn};
+use sha2::{Digest, Sha256};
+use std::fs;
+
+use crate::config::{Config, VERIFY_TYPE_HALO2};
+use crate::errors::VerificationError;
+use crate::models::{Halo2Keys, Halo2KeysInfo, Halo2Proof, VerificationResult};
+use crate::services::halo2_circuits::SquareCircuit;
+
+pub const VERSION: &str = env!("HALO2_PROOFS_VERSION");
+
+// the unwrapped contents of a submission
+pub struct Halo2Payload {
+    pub params: Vec<u8>,
+    pub vk: Vec<u8>,
+    pub proof: Vec<u8>,
+    pub instances: Vec<Vec<String>>,
+}
+
+pub struct CompiledCircuit {
+    pub name: &'static str,
+    // reads params and a verifying key the way verify does, so keys that do
+    // not belong to the circuit are turned away when they are registered
+    pub check_keys: fn(&[u8], &[u8]) -> Result<(), VerificationError>,
+    pub verify: fn(&Halo2Payload) -> Result<bool, VerificationError>,
+}
+
+// halo2 verifying keys do not carry the constraint system, reading one runs
+// the circuit's configure(), so only circuits compiled into the server can be
+// verified. Circuits are added here by name together with the commitment
+// scheme they were proven with
+const CIRCUITS: &[CompiledCircuit] = &[
+    CompiledCircuit {
+        name: "square-kzg-shplonk",
+        check_keys: check_kzg_keys::<SquareCircuit<Fr>>,
+        verify: verify_kzg_shplonk::<SquareCircuit<Fr>>,
+    },
+    CompiledCircuit {
+        name: "square-kzg-gwc",
+        check_keys: check_kzg_keys::<SquareCircuit<Fr>>,
+        verify: verify_kzg_gwc::<SquareCircuit<Fr>>,
+    },
+    CompiledCircuit {
+        name: "square-ipa",
+        check_keys: check_ipa_keys::<SquareCircuit<Fp>>,
+        verify: verify_ipa::<SquareCircuit<Fp>>,
+    },
+];
+
+pub fn circuits() -> Vec<&'static str> {
+    CIRCUITS.iter().map(|circuit| circuit.name).collect()
+}
+
+fn compiled_circuit(name: &str) -> Result<&'static CompiledCircuit, VerificationError> {
+    CIRCUITS
+        .iter()
+        .find(|circuit| circuit.name == name)
+        .ok_or_else(|| {
+            VerificationError::ProgramError(format!(
+                "Halo2 circuit {name} is not one of {:?}",
+                circuits()
+            ))
+        })
+}
+
+// instance values as 0x prefixed big endian hex, the way halo2 prints them
+fn parse_scalar<F: PrimeField<Repr = [u8; 32]>>(value: &str) -> Result<F, String> {
+    let hex_value = value
+        .trim()
+        .strip_prefix("0x")
+        .ok_or_else(|| format!("{value} is not 0x prefixed hex"))?;
+    if hex_value.len() > 64 {
+        return Err(format!("{value} is longer than 32 bytes"));
+    }
+    let mut repr = [0u8; 32];
+    let bytes = hex::decode(format!("{:0>64}", hex_value))
+        .map_err(|err| format!("{value} is not hex: {err}"))?;
+    repr.copy_from_slice(&bytes);
+    repr.reverse();
+    Option::from(F::from_repr(repr)).ok_or_else(|| format!("{value} is not a field element"))
+}
+
+fn parse_instances<F: PrimeField<Repr = [u8; 32]>>(
+    instances: &[Vec<String>],
+) -> Result<Vec<Vec<F>>, VerificationError> {
+    instances
+        .iter()
+        .enumerate()
+        .map(|(column, values)| {
+            values
+                .iter()
+                .map(|value| parse_scalar(value))
+                .collect::<Result<Vec<F>, _>>()
+                .map_err(|err| {
+                    VerificationError::ParseError(format!("instance column {column}: {err}"))
+                })
+        })
+        .collect()
+}
+
+fn params_error(err: std::io::Error) -> VerificationError {
+    VerificationError::KeyError(format!("Error reading Halo2 params: {err}"))
+}
+
+fn vk_error(err: std::io::Error) -> VerificationError {
+    VerificationError::KeyError(format!("Error reading Halo2 verifying key: {err}"))
+}
+
+fn kzg_keys<ConcreteCircuit: Circuit<Fr>>(
+    params: &[u8],
+    vk: &[u8],
+) -> Result<(ParamsKZG<Bn256>, VerifyingKey<G1Affine>), VerificationError> {
+    let params = ParamsKZG::<Bn256>::read(&mut &params[..]).map_err(params_error)?;
+    let vk =
+        VerifyingKey::<G1Affine>::read::<_, ConcreteCircuit>(&mut &vk[..], SerdeFormat::RawBytes)
+            .map_err(vk_error)?;
+    Ok((params, vk))
+}
+
+fn ipa_keys<ConcreteCircuit: Circuit<Fp>>(
+    params: &[u8],
+    vk: &[u8],
+) -> Result<(ParamsIPA<EqAffine>, VerifyingKey<EqAffine>), VerificationError> {
+    let params = ParamsIPA::<EqAffine>::read(&mut &params[..]).map_err(params_error)?;
+    let vk =
+        VerifyingKey::<EqAffine>::read::<_, ConcreteCircuit>(&mut &vk[..], SerdeFormat::RawBytes)
+            .map_err(vk_error)?;
+    Ok((params, vk))
+}
+
+pub fn check_kzg_keys<ConcreteCircuit: Circuit<Fr>>(
+    params: &[u8],
+    vk: &[u8],
+) -> Result<(), VerificationError> {
+    kzg_keys::<ConcreteCircuit>(params, vk).map(|_| ())
+}
+
+pub fn check_ipa_keys<ConcreteCircuit: Circuit<Fp>>(
+    params: &[u8],
+    vk: &[u8],
+) -> Result<(), VerificationError> {
+    ipa_keys::<ConcreteCircuit>(params, vk).map(|_| ())
+}
+
+pub fn verify_kzg_shplonk<ConcreteCircuit: Circuit<Fr>>(
+    payload: &Halo2Payload,
+) -> Result<bool, VerificationError> {
+    let (params, vk) = kzg_keys::<ConcreteCircuit>(&payload.params, &payload.vk)?;
+    let instances = parse_instances::<Fr>(&payload.instances)?;
+    let instances: Vec<&[Fr]> = instances.iter().map(Vec::as_slice).collect();
+    let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(&payload.proof[..]);
+    let result = verify_proof::<KZGCommitmentScheme<Bn256>, VerifierSHPLONK<'_, Bn256>, _, _, _>(
+        &params,
+        &vk,
+        KzgSingleStrategy::new(&params),
+        &[&instances],
+        &mut transcript,
+    );
+    match result {
+        Ok(_) => Ok(true),
+        Err(err) => {
+            warn!("Verification failed: {:?}", err);
+            Ok(false)
+        }
+    }
+}
+
+pub fn verify_kzg_gwc<ConcreteCircuit: Circuit<Fr>>(
+    payload: &Halo2Payload,
+) -> Result<bool, VerificationError> {
+    let (params, vk) = kzg_keys::<ConcreteCircuit>(&payload.params, &payload.vk)?;
+    let instances = parse_instances::<Fr>(&payload.instances)?;
+    let instances: Vec<&[Fr]> = instances.iter().map(Vec::as_slice).collect();
+    let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(&payload.proof[..]);
+    let result = verify_proof::<KZGCommitmentScheme<Bn256>, VerifierGWC<'_, Bn256>, _, _, _>(
+        &params,
+        &vk,
+        KzgSingleStrategy::new(&params),
+        &[&instances],
+        &mut transcript,
+    );
+    match result {
+        Ok(_) => Ok(true),
+        Err(err) => {
+            warn!("Verification failed: {:?}", err);
+            Ok(false)
+        }
+    }
+}
+
+pub fn verify_ipa<ConcreteCircuit: Circuit<Fp>>(
+    payload: &Halo2Payload,
+) -> Result<bool, VerificationError> {
+    let (params, vk) = ipa_keys::<ConcreteCircuit>(&payload.params, &payload.vk)?;
+    let instances = parse_instances::<Fp>(&payload.instances)?;
+    let instances: Vec<&[Fp]> = instances.iter().map(Vec::as_slice).collect();
+    let mut transcript = Blake2bRead::<_, EqAffine, Challenge255<_>>::init(&payload.proof[..]);
+    let result = verify_proof::<IPACommitmentScheme<EqAffine>, VerifierIPA<_>, _, _, _>(
+        &params,
+        &vk,
+        IpaSingleStrategy::new(&params),
+        &[&instances],
+        &mut transcript,
+    );
+    match result {
+        Ok(_) => Ok(true),
+        Err(err) => {
+            warn!("Verification failed: {:?}", err);
+            Ok(false)
+        }
+    }
+}
+
+fn read_key_file(file_path: &str) -> Result<Vec<u8>, VerificationError> {
+    fs::read(file_path)
+        .map_err(|err| VerificationError::IOError(err, format!("Error reading {file_path}")))
+}
+
+fn write_key_file(file_path: &str, bytes: &[u8]) -> Result<(), VerificationError> {
+    fs::write(file_path, bytes)
+        .map_err(|err| VerificationError::IOError(err, format!("Error writing {file_path}")))
+}
+
+// params run to megabytes, so unlike the other backends' keys they are not
+// carried in the job but copied into HALO2_KEY_DIR, out of reach of the
+// retention sweep and of release_files
+pub fn register_keys(keys: &Halo2Keys) -> Result<Halo2KeysInfo, VerificationError> {
+    let circuit = compiled_circuit(&keys.circuit)?;
+    let config = Config::init();
+    let envelope = keys
+        .envelope
+        .unwrap_or(config.envelope_for(VERIFY_TYPE_HALO2));
+    let params = envelope.read_file(&keys.params_file_path)?;
+    let vk = envelope.read_file(&keys.vk_file_path)?;
+    (circuit.check_keys)(&params, &vk)?;
+
+    let vk_hash = hex::encode(Sha256::digest(&vk));
+    fs::create_dir_all(&config.halo2_key_dir).map_err(|err| {
+        VerificationError::IOError(err, "Error creating the Halo2 key directory".to_string())
+    })?;
+    let key_file = |extension: &str| {
+        config
+            .halo2_key_dir
+            .join(format!("{}-{}.{}", circuit.name, vk_hash, extension))
+            .to_string_lossy()
+            .to_string()
+    };
+    let keys_info = Halo2KeysInfo {
+        circuit: circuit.name.to_string(),
+        params_file_path: key_file("params"),
+        vk_file_path: key_file("vk"),
+        vk_hash,
+    };
+    write_key_file(&keys_info.params_file_path, &params)?;
+    write_key_file(&keys_info.vk_file_path, &vk)?;
+    Ok(keys_info)
+}
+
+pub async fn verify(data: &Halo2Proof) -> Result<VerificationResult, VerificationError> {
+    info!("{:?}", data);
+    let circuit = compiled_circuit(&data.circuit)?;
+    let envelope = data
+        .envelope
+        .unwrap_or(Config::init().envelope_for(VERIFY_TYPE_HALO2));
+    let payload = Halo2Payload {
+        params: read_key_file(&data.params_file_path)?,
+        vk: read_key_file(&data.vk_file_path)?,
+        proof: envelope.read_file(&data.proof_file_path)?,
+        instances: data.instances.clone(),
+    };
+    Ok(VerificationResult {
+        is_valid: (circuit.verify)(&payload)?,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::circuit::Value;
+    use halo2_proofs::plonk::{create_proof, keygen_pk, keygen_vk};
+    use halo2_proofs::poly::commitment::{ParamsProver, Prover};
+    use halo2_proofs::poly::ipa::multiopen::ProverIPA;
+    use halo2_proofs::poly::kzg::multiopen::{ProverGWC, ProverSHPLONK};
+    use halo2_proofs::transcript::{Blake2bWrite, TranscriptWriterBuffer};
+    use rand_core::OsRng;
+
+    // rows for the two gate rows plus blinding
+    const K: u32 = 4;
+
+    // the circuits prove a root of 9 is known
+    const ROOT: u64 = 3;
+    const SQUARE: &str = "0x09";
+    const WRONG_SQUARE: &str = "0x0a";
+
+    struct Fixture {
+        circuit: &'static str,
+        params: Vec<u8>,
+        vk: Vec<u8>,
+        proof: Vec<u8>,
+    }
+
+    impl Fixture {
+        fn payload(&self, square: &str) -> Halo2Payload {
+            Halo2Payload {
+                params: self.params.clone(),
+                vk: self.vk.clone(),
+                proof: self.proof.clone(),
+                instances: vec![vec![square.to_string()]],
+            }
+        }
+    }
+
+    fn kzg_fixture<'params, P: Prover<'params, KZGCommitmentScheme<Bn256>>>(
+        circuit: &'static str,
+        params: &'params ParamsKZG<Bn256>,
+    ) -> Fixture {
+        let square_circuit = SquareCircuit {
+            root: Value::known(Fr::from(ROOT)),
+        };
+        let vk = keygen_vk(params, &square_circuit).unwrap();
+        let pk = keygen_pk(params, vk.clone(), &square_circuit).unwrap();
+        let square = [Fr::from(ROOT * ROOT)];
+        let instances: Vec<&[Fr]> = vec![&square];
+        let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+        create_proof::<KZGCommitmentScheme<Bn256>, P, _, _, _, _>(
+            params,
+            &pk,
+            &[square_circuit],
+            &[&instances],
+            OsRng,
+            &mut transcript,
+        )
+        .unwrap();
+        let mut params_bytes = Vec::new();
+        params.write(&mut params_bytes).unwrap();
+        let mut vk_bytes = Vec::new();
+        vk.write(&mut vk_bytes, SerdeFormat::RawBytes).unwrap();
+        Fixture {
+            circuit,
+            params: params_bytes,
+            vk: vk_bytes,
+            proof: transcript.finalize(),
+        }
+    }
+
+    fn ipa_fixture() -> Fixture {
+        let params = ParamsIPA::<EqAffine>::new(K);
+        let square_circuit = SquareCircuit {
+            root: Value::known(Fp::from(ROOT)),
+        };
+        let vk = keygen_vk(&params, &square_circuit).unwrap();
+        let pk = keygen_pk(&params, vk.clone(), &square_circuit).unwrap();
+        let square = [Fp::from(ROOT * ROOT)];
+        let instances: Vec<&[Fp]> = vec![&square];
+        let mut transcript = Blake2bWrite::<_, EqAffine, Challenge255<_>>::init(vec![]);
+        create_proof::<IPACommitmentScheme<EqAffine>, ProverIPA<_>, _, _, _, _>(
+            &params,
+            &pk,
+            &[square_circuit],
+            &[&instances],
+            OsRng,
+            &mut transcript,
+        )
+        .unwrap();
+        let mut params_bytes = Vec::new();
+        params.write(&mut params_bytes).unwrap();
+        let mut vk_bytes = Vec::new();
+        vk.write(&mut vk_bytes, SerdeFormat::RawBytes).unwrap();
+        Fixture {
+            circuit: "square-ipa",
+            params: params_bytes,
+            vk: vk_bytes,
+            proof: transcript.finalize(),
+        }
+    }
+
+    fn fixtures() -> Vec<Fixture> {
+        let params = ParamsKZG::<Bn256>::setup(K, OsRng);
+        vec![
+            kzg_fixture::<ProverSHPLONK<_>>("square-kzg-shplonk", &params),
+            kzg_fixture::<ProverGWC<_>>("square-kzg-gwc", &params),
+            ipa_fixture(),
+        ]
+    }
+
+    #[test]
+    fn compiles_a_circuit_per_scheme() {
+        assert_eq!(
+            circuits(),
+            vec!["square-kzg-shplonk", "square-kzg-gwc", "square-ipa"]
+        );
+        assert!(compiled_circuit("square").is_err());
+    }
+
+    #[test]
+    fn verifies_each_scheme() {
+        for fixture in fixtures() {
+            let circuit = compiled_circuit(fixture.circuit).unwrap();
+            (circuit.check_keys)(&fixture.params, &fixture.vk).unwrap();
+            assert!(
+                (circuit.verify)(&fixture.payload(SQUARE)).unwrap(),
+                "{}",
+                fixture.circuit
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_wrong_instances() {
+        for fixture in fixtures() {
+            let circuit = compiled_circuit(fixture.circuit).unwrap();
+            assert!(
+                !(circuit.verify)(&fixture.payload(WRONG_SQUARE)).unwrap(),
+                "{}",
+                fixture.circuit
+            );
+            assert!((circuit.verify)(&fixture.payload("9")).is_err());
+        }
+    }
+
+    #[test]
+    fn rejects_tampered_proofs() {
+        for mut fixture in fixtures() {
+            let circuit = compiled_circuit(fixture.circuit).unwrap();
+            let middle = fixture.proof.len() / 2;
+            fixture.proof[middle] ^= 1;
+            assert!(
+                !(circuit.verify)(&fixture.payload(SQUARE)).unwrap(),
+                "{}",
+                fixture.circuit
+            );
+            fixture.proof.truncate(middle);
+            assert!(!(circuit.verify)(&fixture.payload(SQUARE)).unwrap());
+        }
+    }
+
+    #[test]
+    fn rejects_truncated_keys() {
+        for fixture in fixtures() {
+            let circuit = compiled_circuit(fixture.circuit).unwrap();
+            let vk = &fixture.vk[..fixture.vk.len() - 1];
+            assert!((circuit.check_keys)(&fixture.params, vk).is_err());
+            let params = &fixture.params[..fixture.params.len() / 2];
+            assert!((circuit.check_keys)(params, &fixture.vk).is_err());
+        }
+    }
+
+    // with no ENVELOPE set the files are read as halo2 wrote them
+    #[actix_web::test]
+    async fn verifies_files_with_the_default_envelope() {
+        let dir = tempfile::tempdir().unwrap();
+        for fixture in fixtures() {
+            let file_path = |extension: &str| {
+                let file_path = dir
+                    .path()
+                    .join(format!("{}.{}", fixture.circuit, extension));
+                file_path.to_string_lossy().to_string()
+            };
+            let data = Halo2Proof {
+                circuit: fixture.circuit.to_string(),
+                params_file_path: file_path("params"),
+                vk_file_path: file_path("vk"),
+                vk_hash: hex::encode(Sha256::digest(&fixture.vk)),
+                proof_file_path: file_path("proof"),
+                instances: vec![vec![SQUARE.to_string()]],
+                envelope: None,
+            };
+            fs::write(&data.params_file_path, &fixture.params).unwrap();
+            fs::write(&data.vk_file_path, &fixture.vk).unwrap();
+            fs::write(&data.proof_file_path, &fixture.proof).unwrap();
+            let verification_result = verify(&data).await.unwrap();
+            assert!(verification_result.is_valid, "{}", fixture.circuit);
+        }
+    }
+}
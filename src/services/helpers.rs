@@ -1,93 +1,385 @@
-use log::{warn, info};
+#[cfg(any(feature = "miden", feature = "risc0"))]
+use base64::engine::general_purpose::STANDARD as BASE64;
+#[cfg(any(feature = "miden", feature = "risc0"))]
+use base64::Engine;
+#[cfg(any(feature = "miden", feature = "risc0"))]
+use log::debug;
+#[cfg(feature = "miden")]
 use miden::StackOutputs;
 use std::fs;
-use std::io::{Read, Write};
-use std::num::ParseIntError;
-use std::fs::{File, OpenOptions};
+use std::path::PathBuf;
 
-use crate::config::Config;
 use crate::errors::VerificationError;
+#[cfg(any(feature = "miden", feature = "risc0"))]
+use crate::models::{Proof, ProofBytes};
+use crate::storage::SPOOL_ROOTS;
 
+#[cfg(any(feature = "miden", feature = "risc0"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofFormat {
+    // {"proof": [1, 2, 3]}
+    JsonArray,
+    // {"proof": "AQID"}
+    JsonBase64,
+    // the serialized proof itself
+    Binary,
+}
+
+// modulus of the 64-bit field miden stack elements live in
+#[cfg(feature = "miden")]
+const FIELD_MODULUS: u64 = 0xFFFF_FFFF_0000_0001;
+
+#[cfg(feature = "miden")]
 #[derive(serde::Deserialize, serde::Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct Outputs {
     pub stack: Vec<u64>,
     pub overflow_addrs: Option<Vec<u64>>,
 }
 
-pub fn string_to_u64_vec(s: &str) -> Result<Vec<u64>, ParseIntError> {
+#[cfg(feature = "miden")]
+pub fn string_to_u64_vec(s: &str) -> Result<Vec<u64>, String> {
     let mut result = Vec::new();
-    for num_str in s.trim_matches(|c| c == '[' || c == ']').split(',') {
-        let num = num_str.trim().parse::<u64>().unwrap_or(0); // Parse, or set to 0 on error
+    let trimmed = s.trim().trim_matches(|c| c == '[' || c == ']').trim();
+    if trimmed.is_empty() {
+        return Ok(result);
+    }
+    for (index, num_str) in trimmed.split(',').enumerate() {
+        let num = num_str
+            .trim()
+            .parse::<u64>()
+            .map_err(|err| format!("element {} ({:?}): {}", index, num_str.trim(), err))?;
         result.push(num);
     }
     Ok(result)
 }
 
+#[cfg(feature = "miden")]
 pub fn deserialize_stack_outputs(outputs_as_str: &str) -> Result<StackOutputs, String> {
     let outputs_as_json: Outputs =
         serde_json::from_str(outputs_as_str).map_err(|e| e.to_string())?;
 
-    let outputs = StackOutputs::new(
-        outputs_as_json.stack,
-        outputs_as_json.overflow_addrs.unwrap_or(vec![]),
-    )
-    .unwrap();
+    if let Some(index) = outputs_as_json
+        .stack
+        .iter()
+        .position(|value| *value >= FIELD_MODULUS)
+    {
+        return Err(format!(
+            "stack element {} ({}) is not a valid field element",
+            index, outputs_as_json.stack[index]
+        ));
+    }
+    let overflow_addrs = outputs_as_json.overflow_addrs.unwrap_or(vec![]);
+    if let Some(index) = overflow_addrs
+        .iter()
+        .position(|value| *value >= FIELD_MODULUS)
+    {
+        return Err(format!(
+            "overflow address {} ({}) is not a valid field element",
+            index, overflow_addrs[index]
+        ));
+    }
+
+    let outputs = StackOutputs::new(outputs_as_json.stack, overflow_addrs)
+        .map_err(|err| format!("invalid stack outputs: {err:?}"))?;
 
     Ok(outputs)
 }
 
-pub async fn handle_proof_bytes(proof_file_path: &str) -> Result<String, VerificationError> {
-    let mut file = fs::File::open(&proof_file_path)
-        .map_err(|err| VerificationError::IOError(err, "Error opening receipt file".to_string()))?;
-
-    let mut buffer: Vec<u8> = vec![0; 32];
+#[cfg(any(feature = "sp1", feature = "miden", feature = "risc0"))]
+pub fn is_json_object(payload: &[u8]) -> bool {
+    payload
+        .iter()
+        .find(|byte| !byte.is_ascii_whitespace())
+        .map_or(false, |byte| *byte == b'{')
+}
 
-    file.read_exact(&mut buffer).map_err(|err| {
-        VerificationError::IOError(err, "Error reading first 32 bytes".to_string())
-    })?;
+// unwraps the {"proof": ...} JSON wrapper when there is one, otherwise the
+// payload is taken to already be the serialized proof
+#[cfg(any(feature = "miden", feature = "risc0"))]
+pub fn decode_proof_bytes(payload: Vec<u8>) -> Result<(ProofFormat, Vec<u8>), VerificationError> {
+    if is_json_object(&payload) {
+        match serde_json::from_slice::<Proof>(&payload) {
+            Ok(Proof {
+                proof: ProofBytes::Array(bytes),
+            }) => return Ok((ProofFormat::JsonArray, bytes)),
+            Ok(Proof {
+                proof: ProofBytes::Base64(encoded),
+            }) => {
+                let bytes = BASE64.decode(encoded.trim()).map_err(|err| {
+                    VerificationError::ParseError(format!("Error decoding base64 proof: {err}"))
+                })?;
+                return Ok((ProofFormat::JsonBase64, bytes));
+            }
+            Err(err) => debug!("Proof is not a JSON proof wrapper, reading as binary: {err}"),
+        }
+    }
+    Ok((ProofFormat::Binary, payload))
+}
 
-    let mut remaining_content = String::new();
-    file.read_to_string(&mut remaining_content).map_err(|err| {
-        VerificationError::IOError(err, "Error reading remaining content".to_string())
-    })?;
+// resolves symlinks and `..` before checking the file sits under one of the
+// configured spool roots, an empty list of roots allows any path
+pub fn validate_spool_path(file_path: &str) -> Result<String, VerificationError> {
+    let spool_roots = SPOOL_ROOTS
+        .as_ref()
+        .map_err(|err| VerificationError::PathError(err.clone()))?;
+    confine_path(file_path, spool_roots)
+}
 
-    Ok(remaining_content)
+// the roots are expected to be canonical already
+fn confine_path(file_path: &str, spool_roots: &[PathBuf]) -> Result<String, VerificationError> {
+    let canonical_path = fs::canonicalize(file_path)
+        .map_err(|err| VerificationError::IOError(err, format!("Error resolving {file_path}")))?;
+    if !spool_roots.is_empty()
+        && !spool_roots
+            .iter()
+            .any(|root| canonical_path.starts_with(root))
+    {
+        return Err(VerificationError::PathError(format!(
+            "{} is outside the spool roots",
+            canonical_path.display()
+        )));
+    }
+    let metadata = fs::metadata(&canonical_path)
+        .map_err(|err| VerificationError::IOError(err, format!("Error reading {file_path}")))?;
+    if !metadata.is_file() {
+        return Err(VerificationError::PathError(format!(
+            "{} is not a regular file",
+            canonical_path.display()
+        )));
+    }
+    Ok(canonical_path.to_string_lossy().to_string())
 }
 
-pub fn handle_delete_files(files: &Vec<&String>) {
-    let config = Config::init();
-    if config.delete_files {
-        for file in files {
-            let _ =
-                std::fs::remove_file(file).map_err(|err| warn!("Error deleting file: {:?}", err));
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::os::unix::fs::symlink;
+    use std::path::Path;
+
+    fn spool() -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap().join("spool");
+        fs::create_dir(&root).unwrap();
+        fs::write(root.join("proof.bin"), b"proof").unwrap();
+        fs::write(dir.path().join("secret.bin"), b"secret").unwrap();
+        (dir, root)
+    }
+
+    fn path_str(path: &Path) -> &str {
+        path.to_str().unwrap()
+    }
+
+    #[test]
+    fn confines_files_to_the_roots() {
+        let (_dir, root) = spool();
+        let proof = root.join("proof.bin");
+        assert_eq!(
+            confine_path(path_str(&proof), &[root.clone()]).unwrap(),
+            path_str(&proof)
+        );
+        let err = confine_path(path_str(&proof), &[root.join("other")]).unwrap_err();
+        assert!(matches!(err, VerificationError::PathError(_)));
+    }
+
+    #[test]
+    fn allows_any_path_without_roots() {
+        let (dir, _root) = spool();
+        let secret = fs::canonicalize(dir.path().join("secret.bin")).unwrap();
+        assert_eq!(
+            confine_path(path_str(&secret), &[]).unwrap(),
+            path_str(&secret)
+        );
+    }
+
+    #[test]
+    fn rejects_dot_dot_traversal() {
+        let (_dir, root) = spool();
+        let escaped = root.join("..").join("secret.bin");
+        let err = confine_path(path_str(&escaped), &[root.clone()]).unwrap_err();
+        assert!(matches!(err, VerificationError::PathError(_)));
+        // `..` that stays inside the root is fine
+        let inside = root.join("..").join("spool").join("proof.bin");
+        assert_eq!(
+            confine_path(path_str(&inside), &[root.clone()]).unwrap(),
+            path_str(&root.join("proof.bin"))
+        );
+    }
+
+    #[test]
+    fn rejects_symlinks_out_of_the_roots() {
+        let (dir, root) = spool();
+        let link = root.join("link.bin");
+        symlink(dir.path().join("secret.bin"), &link).unwrap();
+        let err = confine_path(path_str(&link), &[root.clone()]).unwrap_err();
+        assert!(matches!(err, VerificationError::PathError(_)));
+        let linked_dir = root.join("linked");
+        symlink(dir.path(), &linked_dir).unwrap();
+        let err =
+            confine_path(path_str(&linked_dir.join("secret.bin")), &[root.clone()]).unwrap_err();
+        assert!(matches!(err, VerificationError::PathError(_)));
+    }
+
+    #[test]
+    fn follows_symlinks_inside_the_roots() {
+        let (_dir, root) = spool();
+        let link = root.join("link.bin");
+        symlink(root.join("proof.bin"), &link).unwrap();
+        assert_eq!(
+            confine_path(path_str(&link), &[root.clone()]).unwrap(),
+            path_str(&root.join("proof.bin"))
+        );
+    }
+
+    #[test]
+    fn rejects_missing_files() {
+        let (_dir, root) = spool();
+        let missing = root.join("missing.bin");
+        let err = confine_path(path_str(&missing), &[root.clone()]).unwrap_err();
+        assert!(matches!(err, VerificationError::IOError(..)));
+        let dangling = root.join("dangling.bin");
+        symlink(root.join("missing.bin"), &dangling).unwrap();
+        let err = confine_path(path_str(&dangling), &[root.clone()]).unwrap_err();
+        assert!(matches!(err, VerificationError::IOError(..)));
+    }
+
+    #[test]
+    fn rejects_directories() {
+        let (_dir, root) = spool();
+        let err = confine_path(path_str(&root), &[root.clone()]).unwrap_err();
+        assert!(matches!(err, VerificationError::PathError(_)));
+    }
+
+    #[cfg(any(feature = "miden", feature = "risc0"))]
+    #[test]
+    fn decodes_json_array_proofs() {
+        let (format, bytes) = decode_proof_bytes(br#"{"proof": [1, 2, 3]}"#.to_vec()).unwrap();
+        assert_eq!(format, ProofFormat::JsonArray);
+        assert_eq!(bytes, vec![1, 2, 3]);
+        let (format, bytes) = decode_proof_bytes(b"\n  {\"proof\":[]}\n".to_vec()).unwrap();
+        assert_eq!(format, ProofFormat::JsonArray);
+        assert!(bytes.is_empty());
+    }
+
+    #[cfg(any(feature = "miden", feature = "risc0"))]
+    #[test]
+    fn decodes_json_base64_proofs() {
+        let (format, bytes) = decode_proof_bytes(br#"{"proof": "AQID"}"#.to_vec()).unwrap();
+        assert_eq!(format, ProofFormat::JsonBase64);
+        assert_eq!(bytes, vec![1, 2, 3]);
+        let (format, bytes) = decode_proof_bytes(br#"{"proof": " AQID\n"}"#.to_vec()).unwrap();
+        assert_eq!(format, ProofFormat::JsonBase64);
+        assert_eq!(bytes, vec![1, 2, 3]);
+    }
+
+    #[cfg(any(feature = "miden", feature = "risc0"))]
+    #[test]
+    fn rejects_bad_base64_proofs() {
+        let err = decode_proof_bytes(br#"{"proof": "AQ!D"}"#.to_vec()).unwrap_err();
+        assert!(matches!(err, VerificationError::ParseError(_)));
+    }
+
+    #[cfg(any(feature = "miden", feature = "risc0"))]
+    #[test]
+    fn passes_binary_proofs_through() {
+        for payload in [vec![], vec![0u8, 1, 2, 3], b"[1, 2, 3]".to_vec()] {
+            let (format, bytes) = decode_proof_bytes(payload.clone()).unwrap();
+            assert_eq!(format, ProofFormat::Binary);
+            assert_eq!(bytes, payload);
         }
     }
-}
 
-pub fn handle_bytes(source_file: &str) -> std::io::Result<()> {
-    // target file is source file + 1;
-    let target_file = format!("{}.tmp", source_file);
-    let mut source = File::open(source_file)?;
-    let mut target = OpenOptions::new()
-        .create(true)
-        .write(true)
-        .open(&target_file)?;
-
-    let mut buffer = [0; 4096]; // Adjust buffer size for performance if needed
-
-    // Skip first 32 bytes
-    source.read(&mut buffer[..32])?;
-
-    // Read remaining content and write to new file
-    loop {
-        let read_bytes = source.read(&mut buffer)?;
-        if read_bytes == 0 {
-            break;
+    // a binary proof that happens to start with `{`, or JSON that isn't the
+    // proof wrapper, is read as binary rather than rejected
+    #[cfg(any(feature = "miden", feature = "risc0"))]
+    #[test]
+    fn reads_ambiguous_payloads_as_binary() {
+        for payload in [
+            b"{\x00\x01\x02".to_vec(),
+            b"  {".to_vec(),
+            br#"{"receipt": [1, 2, 3]}"#.to_vec(),
+            br#"{"proof": [1, 256]}"#.to_vec(),
+            br#"{"proof": {"bytes": "AQID"}}"#.to_vec(),
+        ] {
+            let (format, bytes) = decode_proof_bytes(payload.clone()).unwrap();
+            assert_eq!(format, ProofFormat::Binary);
+            assert_eq!(bytes, payload);
         }
-        target.write_all(&buffer[..read_bytes])?;
     }
 
-    info!("Successfully created '{}' excluding first 32 bytes of '{}'", target_file, source_file);
+    #[cfg(feature = "miden")]
+    #[test]
+    fn parses_u64_lists() {
+        assert_eq!(string_to_u64_vec("[1,2,3]").unwrap(), vec![1, 2, 3]);
+        assert_eq!(
+            string_to_u64_vec(" [ 1 , 2 ,3 ] \n").unwrap(),
+            vec![1, 2, 3]
+        );
+        assert_eq!(string_to_u64_vec("4, 5").unwrap(), vec![4, 5]);
+        assert_eq!(
+            string_to_u64_vec(&format!("[{}]", u64::MAX)).unwrap(),
+            vec![u64::MAX]
+        );
+    }
+
+    #[cfg(feature = "miden")]
+    #[test]
+    fn parses_empty_u64_lists() {
+        for input in ["", "   ", "[]", "[ ]", " \t[]\n"] {
+            assert_eq!(string_to_u64_vec(input).unwrap(), Vec::<u64>::new());
+        }
+    }
+
+    #[cfg(feature = "miden")]
+    #[test]
+    fn rejects_bad_u64_lists() {
+        let overflow = format!("[1,{}]", u64::MAX as u128 + 1);
+        assert!(string_to_u64_vec(&overflow)
+            .unwrap_err()
+            .starts_with("element 1 "));
+        for input in ["[1,,2]", "[1,2,]", "[-1]", "[1.5]", "[0x10]", "[1 2]"] {
+            assert!(string_to_u64_vec(input).is_err(), "{input}");
+        }
+    }
 
-    Ok(())
+    #[cfg(feature = "miden")]
+    #[test]
+    fn deserializes_stack_outputs() {
+        assert!(deserialize_stack_outputs(r#"{"stack": [1, 2, 3]}"#).is_ok());
+        assert!(deserialize_stack_outputs(r#"{"stack": [], "overflow_addrs": []}"#).is_ok());
+        let largest = FIELD_MODULUS - 1;
+        assert!(deserialize_stack_outputs(&format!(r#"{{"stack": [{largest}]}}"#)).is_ok());
+    }
+
+    #[cfg(feature = "miden")]
+    #[test]
+    fn rejects_stack_outputs_outside_the_field() {
+        let err = deserialize_stack_outputs(&format!(r#"{{"stack": [1, {FIELD_MODULUS}]}}"#))
+            .unwrap_err();
+        assert!(err.starts_with("stack element 1 "), "{err}");
+        let err = deserialize_stack_outputs(&format!(
+            r#"{{"stack": [1], "overflow_addrs": [{}]}}"#,
+            u64::MAX
+        ))
+        .unwrap_err();
+        assert!(err.starts_with("overflow address 0 "), "{err}");
+    }
+
+    #[cfg(feature = "miden")]
+    #[test]
+    fn rejects_malformed_stack_outputs() {
+        for input in [
+            "",
+            "   ",
+            "[]",
+            "{}",
+            r#"{"stack": "1,2"}"#,
+            r#"{"stack": [-1]}"#,
+            r#"{"stack": [18446744073709551616]}"#,
+            r#"{"stack": [1], "extra": true}"#,
+        ] {
+            assert!(deserialize_stack_outputs(input).is_err(), "{input}");
+        }
+    }
 }
@@ -1,38 +1,67 @@
 use jolt::{tracer, Jolt, Proof, RV32IJoltVM};
 use log::{info, warn};
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
 
-use super::helpers::{handle_bytes, handle_delete_files};
+use super::envelope::Envelope;
+use crate::config::{Config, VERIFY_TYPE_JOLT};
 use crate::errors::VerificationError;
 use crate::models::{JoltProof, VerificationResult};
 
+pub const VERSION: &str = env!("JOLT_SDK_VERSION");
+
+// jolt only loads proofs and ELFs from disk, so the unwrapped payloads are
+// written into a private directory that is removed when it goes out of scope
+fn write_payload(
+    spool: &Path,
+    name: &str,
+    source_file: &str,
+    envelope: Envelope,
+) -> Result<PathBuf, VerificationError> {
+    let payload = envelope.read_file(source_file)?;
+    let target_file = spool.join(name);
+    fs::write(&target_file, payload).map_err(|err| {
+        VerificationError::IOError(err, format!("Error writing {}", target_file.display()))
+    })?;
+    Ok(target_file)
+}
+
 pub async fn verify(data: &JoltProof) -> Result<VerificationResult, VerificationError> {
     info!("{:?}", data);
+    let envelope = data
+        .envelope
+        .unwrap_or(Config::init().envelope_for(VERIFY_TYPE_JOLT));
 
-    handle_bytes(&data.proof_file_path).map_err(|err| {
-        return VerificationError::IOError(err, "Error reading proof file".to_string());
+    let spool = TempDir::with_prefix("jugalbandi-jolt-").map_err(|err| {
+        VerificationError::IOError(err, "Error creating temporary directory".to_string())
     })?;
 
-    let proof = Proof::from_file(&format!("{}.tmp", &data.proof_file_path)).unwrap();
+    let proof_file = write_payload(spool.path(), "proof", &data.proof_file_path, envelope)?;
 
-    handle_bytes(&data.elf_file_path).map_err(|err| {
-        return VerificationError::IOError(err, "Error reading elf file".to_string());
+    let proof = Proof::from_file(&proof_file.to_string_lossy().to_string()).map_err(|err| {
+        VerificationError::ProofError(format!("Error deserializing Jolt proof: {err:?}"))
     })?;
 
-    let (byte_code, memory_init) =
-        tracer::decode(&PathBuf::from(&format!("{}.tmp", &data.elf_file_path)));
+    let elf_file = write_payload(spool.path(), "elf", &data.elf_file_path, envelope)?;
+
+    let (byte_code, memory_init) = tracer::decode(&elf_file);
 
     let preproccessing = RV32IJoltVM::preprocess(byte_code, memory_init, 1 << 20, 1 << 20, 1 << 20);
 
     let verification_result = RV32IJoltVM::verify(preproccessing, proof.proof, proof.commitments);
 
-    handle_delete_files(&vec![&data.proof_file_path, &data.elf_file_path]);
-
     match verification_result {
-        Ok(_) => Ok(VerificationResult { is_valid: true }),
+        Ok(_) => Ok(VerificationResult {
+            is_valid: true,
+            ..Default::default()
+        }),
         Err(err) => {
             warn!("Verification failed: {:?}", err);
-            Ok(VerificationResult { is_valid: false })
+            Ok(VerificationResult {
+                is_valid: false,
+                ..Default::default()
+            })
         }
     }
 }
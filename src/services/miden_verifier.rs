@@ -1,60 +1,164 @@
 use log::{info, warn};
-use miden::{Digest, ExecutionProof, Kernel, ProgramInfo, StackInputs};
-use std::fs;
+use miden::utils::Deserializable;
+use miden::{Assembler, Digest, ExecutionProof, Kernel, Program, ProgramInfo, StackInputs};
+use std::collections::HashMap;
 
-use super::helpers::{deserialize_stack_outputs, handle_delete_files, string_to_u64_vec};
+use super::envelope::Envelope;
+use super::helpers::{decode_proof_bytes, deserialize_stack_outputs, string_to_u64_vec};
+use crate::config::{Config, VERIFY_TYPE_MIDEN};
 use crate::errors::VerificationError;
-use crate::models::{MidenProof, Proof, VerificationResult};
+use crate::models::{MidenProgramInfo, MidenProof, VerificationResult};
+
+pub const VERSION: &str = env!("MIDEN_VM_VERSION");
+
+pub fn compile_program(
+    program_file_path: &str,
+    format: &str,
+    envelope: Envelope,
+) -> Result<MidenProgramInfo, VerificationError> {
+    let bytes = envelope.read_file(program_file_path)?;
+    // the hash is always derived from the program itself, never taken from
+    // the submitted bytes
+    let program = match format {
+        "masm" => {
+            let source = String::from_utf8(bytes).map_err(|err| {
+                VerificationError::ProgramError(format!("MASM source is not UTF-8: {err}"))
+            })?;
+            Assembler::default().compile(&source).map_err(|err| {
+                VerificationError::ProgramError(format!("Error compiling MASM source: {err}"))
+            })?
+        }
+        "program" => Program::read_from_bytes(&bytes).map_err(|err| {
+            VerificationError::ProgramError(format!("Error deserializing program: {err:?}"))
+        })?,
+        _ => {
+            return Err(VerificationError::ProgramError(format!(
+                "Unknown Miden program format {format}"
+            )))
+        }
+    };
+    let program_info = ProgramInfo::new(program.hash(), program.kernel().clone());
+    Ok(MidenProgramInfo {
+        program_hash: String::from(*program_info.program_hash()),
+        kernel_procedures: program_info
+            .kernel()
+            .proc_hashes()
+            .iter()
+            .map(|proc_hash| String::from(*proc_hash))
+            .collect(),
+    })
+}
+
+// a registered program name wins, and a claimed hash or kernel must agree with it
+pub fn resolve_program(
+    programs: &HashMap<String, MidenProgramInfo>,
+    program_name: Option<&String>,
+    program_hash: Option<&String>,
+    kernel_procedures: Option<&Vec<String>>,
+) -> Result<(String, Option<Vec<String>>), VerificationError> {
+    match program_name {
+        Some(program_name) => {
+            let registered = programs.get(program_name).ok_or_else(|| {
+                VerificationError::ProgramError(format!("Unknown Miden program {program_name}"))
+            })?;
+            if let Some(program_hash) = program_hash {
+                if !program_hash.eq_ignore_ascii_case(&registered.program_hash) {
+                    return Err(VerificationError::DigestError(
+                        "Program hash does not match registered program".to_string(),
+                    ));
+                }
+            }
+            if let Some(kernel_procedures) = kernel_procedures {
+                if *kernel_procedures != registered.kernel_procedures {
+                    return Err(VerificationError::DigestError(
+                        "Kernel procedures do not match registered program".to_string(),
+                    ));
+                }
+            }
+            Ok((
+                registered.program_hash.clone(),
+                Some(registered.kernel_procedures.clone()),
+            ))
+        }
+        None => {
+            let program_hash = program_hash.ok_or_else(|| {
+                VerificationError::DigestError("Missing program hash or program name".to_string())
+            })?;
+            Ok((program_hash.clone(), kernel_procedures.cloned()))
+        }
+    }
+}
 
 pub async fn verify(data: &MidenProof) -> Result<VerificationResult, VerificationError> {
     info!("{:?}", data);
     let program_hash = Digest::try_from(data.program_hash.clone()).map_err(|err| {
         return VerificationError::DigestError(format!("Error parsing program hash: {err:?}"));
     })?;
-    let program_info = ProgramInfo::new(program_hash, Kernel::default());
+    let kernel = match &data.kernel_procedures {
+        Some(procedures) => {
+            let mut proc_hashes = Vec::with_capacity(procedures.len());
+            for procedure in procedures {
+                let proc_hash = Digest::try_from(procedure.clone()).map_err(|err| {
+                    return VerificationError::DigestError(format!(
+                        "Error parsing kernel procedure root {procedure}: {err:?}"
+                    ));
+                })?;
+                proc_hashes.push(proc_hash);
+            }
+            Kernel::new(&proc_hashes)
+        }
+        None => Kernel::default(),
+    };
+    let program_info = ProgramInfo::new(program_hash, kernel);
     let inputs_u64 = string_to_u64_vec(&data.inputs_stack).map_err(|err| {
-        return VerificationError::ParseError(format!("Error parsing inputs stack JSON: {err:?}"));
+        return VerificationError::ParseError(format!("Invalid inputs stack {err}"));
     })?;
     info!("Program inputs: {:?}", inputs_u64);
     let stack_inputs = StackInputs::try_from_values(inputs_u64).map_err(|err| {
         return VerificationError::ParseError(format!("Error parsing inputs stack JSON: {err:?}"));
     })?;
     let stack_outputs = deserialize_stack_outputs(&data.outputs_stack).map_err(|err| {
-        return VerificationError::ParseError(format!("Error parsing outputs stack JSON: {err:?}"));
+        return VerificationError::ParseError(format!("Error parsing outputs stack JSON: {err}"));
     })?;
-    // uncomment this when running with hypersdk
-    // let proof = handle_proof_bytes(&data.proof_file_path)
-    //     .await
-    //     .map_err(|err| {
-    //         return err;
-    //     })?;
-    // comment this when running with hypersdk
-    let proof = fs::read_to_string(&data.proof_file_path).map_err(|err| {
-        return VerificationError::IOError(err, "Error reading proof file".to_string());
+    let config = Config::init();
+    let envelope = data
+        .envelope
+        .unwrap_or(config.envelope_for(VERIFY_TYPE_MIDEN));
+    let proof = envelope.read_file(&data.proof_file_path)?;
+    let (format, proof_bytes) = decode_proof_bytes(proof)?;
+    info!("Reading Miden proof as {:?}", format);
+    let proof = ExecutionProof::from_bytes(&proof_bytes).map_err(|err| {
+        VerificationError::ProofError(format!("Error deserializing Miden proof: {err:?}"))
     })?;
-    let parsed_data: Proof = match serde_json::from_str(&proof) {
-        Ok(x) => x,
-        Err(err) => {
-            return Err(VerificationError::JSONError(
-                err,
-                "Error parsing proof JSON".to_string(),
-            ))
-        }
-    };
-    let proof = ExecutionProof::from_bytes(&parsed_data.proof).unwrap();
 
     info!(
         "{:?}, {:?}, {:?}",
         stack_inputs, stack_outputs, program_info
     );
+    let min_security_level = config
+        .miden_program_security_levels
+        .get(&data.program_hash)
+        .copied()
+        .unwrap_or(config.miden_min_security_level);
     let verification_result = miden::verify(program_info, stack_inputs, stack_outputs, proof);
-    let is_valid = match verification_result {
-        Ok(x) => x == 96,
+    let (is_valid, security_level) = match verification_result {
+        Ok(x) => {
+            if x < min_security_level {
+                warn!(
+                    "Proof security level {} is below the required {}",
+                    x, min_security_level
+                );
+            }
+            (x >= min_security_level, Some(x))
+        }
         Err(err) => {
             warn!("Verification failed : {:?}", err);
-            false
+            (false, None)
         }
     };
-    handle_delete_files(&vec![&data.proof_file_path]);
-    return Ok(VerificationResult { is_valid });
+    return Ok(VerificationResult {
+        is_valid,
+        security_level,
+        ..Default::default()
+    });
 }
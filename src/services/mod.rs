@@ -1,5 +1,325 @@
+#[cfg(feature = "miden")]
 pub mod miden_verifier;
+#[cfg(feature = "risc0")]
 pub mod risc0_verifier;
+#[cfg(feature = "risc0-v1")]
+pub mod risc0_v1_verifier;
+#[cfg(feature = "sp1-v2")]
+pub mod sp1_v2_verifier;
+#[cfg(feature = "sp1")]
 pub mod sp1_verifier;
+pub mod envelope;
+#[cfg(feature = "groth16")]
+pub mod groth16_verifier;
+#[cfg(feature = "halo2")]
+pub mod halo2_circuits;
+#[cfg(feature = "halo2")]
+pub mod halo2_verifier;
 pub mod helpers;
+#[cfg(feature = "jolt")]
 pub mod jolt_verifier;
+#[cfg(feature = "plonky2")]
+pub mod plonky2_verifier;
+
+use std::any::Any;
+use std::future::Future;
+
+use crate::config::{self, backend_name, Config};
+use crate::errors::VerificationError;
+use crate::models::{Backend, VerificationJob, VerificationResult};
+
+// the proof systems this binary was built with
+pub fn backends() -> Vec<Backend> {
+    let mut backends = Vec::new();
+    #[cfg(feature = "sp1")]
+    backends.push(Backend::new(config::VERIFY_TYPE_SP1));
+    #[cfg(feature = "miden")]
+    backends.push(Backend::new(config::VERIFY_TYPE_MIDEN));
+    #[cfg(feature = "risc0")]
+    backends.push(Backend::new(config::VERIFY_TYPE_RISC0));
+    #[cfg(feature = "jolt")]
+    backends.push(Backend::new(config::VERIFY_TYPE_JOLT));
+    #[cfg(feature = "groth16")]
+    backends.push(Backend::new(config::VERIFY_TYPE_GROTH16));
+    #[cfg(feature = "plonky2")]
+    backends.push(Backend::new(config::VERIFY_TYPE_PLONKY2));
+    // halo2 proofs can only be checked against circuits compiled in
+    #[cfg(feature = "halo2")]
+    if !halo2_verifier::circuits().is_empty() {
+        backends.push(Backend::new(config::VERIFY_TYPE_HALO2));
+    }
+    backends
+}
+
+pub fn is_compiled(verify_type: u8) -> bool {
+    backends()
+        .iter()
+        .any(|backend| backend.verify_type == verify_type)
+}
+
+// versions of a backend compiled side by side, the first one is the default
+// unless BACKEND_VERSIONS says otherwise. build.rs reads them from Cargo.lock,
+// registry crates by version and git checkouts by commit
+pub fn versions(verify_type: u8) -> Vec<&'static str> {
+    let mut versions = Vec::new();
+    match verify_type {
+        #[cfg(feature = "sp1")]
+        config::VERIFY_TYPE_SP1 => {
+            versions.push(sp1_verifier::VERSION);
+            #[cfg(feature = "sp1-v2")]
+            versions.push(sp1_v2_verifier::VERSION);
+        }
+        #[cfg(feature = "miden")]
+        config::VERIFY_TYPE_MIDEN => versions.push(miden_verifier::VERSION),
+        #[cfg(feature = "risc0")]
+        config::VERIFY_TYPE_RISC0 => {
+            versions.push(risc0_verifier::VERSION);
+            #[cfg(feature = "risc0-v1")]
+            versions.push(risc0_v1_verifier::VERSION);
+        }
+        #[cfg(feature = "jolt")]
+        config::VERIFY_TYPE_JOLT => versions.push(jolt_verifier::VERSION),
+        #[cfg(feature = "groth16")]
+        config::VERIFY_TYPE_GROTH16 => versions.push(groth16_verifier::VERSION),
+        #[cfg(feature = "plonky2")]
+        config::VERIFY_TYPE_PLONKY2 => versions.push(plonky2_verifier::VERSION),
+        #[cfg(feature = "halo2")]
+        config::VERIFY_TYPE_HALO2 => versions.push(halo2_verifier::VERSION),
+        _ => {}
+    }
+    versions
+}
+
+// picks the version a submission is verified with from a "1.0", "1.x" or
+// "risc0@1.0" selector, falling back to the configured default
+pub fn resolve_version(
+    verify_type: u8,
+    selector: Option<&str>,
+) -> Result<&'static str, VerificationError> {
+    let configured = Config::init()
+        .backend_versions
+        .get(backend_name(verify_type));
+    select_version(
+        verify_type,
+        &versions(verify_type),
+        selector,
+        configured.map(String::as_str),
+    )
+}
+
+fn select_version(
+    verify_type: u8,
+    available: &[&'static str],
+    selector: Option<&str>,
+    configured: Option<&str>,
+) -> Result<&'static str, VerificationError> {
+    let backend = backend_name(verify_type);
+    let requested = match selector {
+        Some(selector) => match selector.split_once('@') {
+            Some((name, version)) if name == backend => Some(version),
+            Some((name, _)) => {
+                return Err(VerificationError::VersionMismatch(format!(
+                    "{selector} selects {name}, not {backend}"
+                )))
+            }
+            None => Some(selector),
+        },
+        None => configured,
+    };
+    let requested = match requested {
+        Some(requested) => requested,
+        None => {
+            return available
+                .first()
+                .copied()
+                .ok_or_else(|| VerificationError::InvalidVerifyType(verify_type))
+        }
+    };
+    let requested = requested.trim_end_matches(".x");
+    available
+        .iter()
+        .copied()
+        .find(|version| *version == requested || version.starts_with(&format!("{requested}.")))
+        .ok_or_else(|| {
+            VerificationError::VersionMismatch(format!(
+                "{backend}@{requested} is not compiled in, available versions are {:?}",
+                available
+            ))
+        })
+}
+
+pub async fn verify(job: VerificationJob) -> Result<VerificationResult, VerificationError> {
+    match job {
+        #[cfg(feature = "sp1-v2")]
+        VerificationJob::Sp1(proof) if proof.version == sp1_v2_verifier::VERSION => {
+            sp1_v2_verifier::verify(&proof).await
+        }
+        #[cfg(feature = "sp1")]
+        VerificationJob::Sp1(proof) => sp1_verifier::verify(&proof).await,
+        #[cfg(feature = "miden")]
+        VerificationJob::Miden(proof) => miden_verifier::verify(&proof).await,
+        #[cfg(feature = "risc0-v1")]
+        VerificationJob::Risc0(proof) if proof.version == risc0_v1_verifier::VERSION => {
+            risc0_v1_verifier::verify(&proof).await
+        }
+        #[cfg(feature = "risc0")]
+        VerificationJob::Risc0(proof) => risc0_verifier::verify(&proof).await,
+        #[cfg(feature = "jolt")]
+        VerificationJob::Jolt(proof) => jolt_verifier::verify(&proof).await,
+        #[cfg(feature = "groth16")]
+        VerificationJob::Groth16(proof) => groth16_verifier::verify(&proof).await,
+        #[cfg(feature = "plonky2")]
+        VerificationJob::Plonky2(proof) => plonky2_verifier::verify(&proof).await,
+        #[cfg(feature = "halo2")]
+        VerificationJob::Halo2(proof) => halo2_verifier::verify(&proof).await,
+    }
+}
+
+// runs the backend on its own task so a panic while verifying becomes an
+// error instead of taking the queue worker down with it
+pub async fn verify_isolated(
+    job: VerificationJob,
+) -> Result<VerificationResult, VerificationError> {
+    isolate(verify(job)).await
+}
+
+async fn isolate<F>(verification: F) -> Result<VerificationResult, VerificationError>
+where
+    F: Future<Output = Result<VerificationResult, VerificationError>> + Send + 'static,
+{
+    match tokio::spawn(verification).await {
+        Ok(verification_result) => verification_result,
+        Err(err) if err.is_panic() => {
+            Err(VerificationError::Panic(panic_message(err.into_panic())))
+        }
+        Err(err) => Err(VerificationError::Panic(err.to_string())),
+    }
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn panics_with_str() -> Result<VerificationResult, VerificationError> {
+        panic!("bad proof")
+    }
+
+    async fn panics_with_string(index: usize) -> Result<VerificationResult, VerificationError> {
+        panic!("bad proof {index}")
+    }
+
+    async fn panics_with_value() -> Result<VerificationResult, VerificationError> {
+        std::panic::panic_any(7u8)
+    }
+
+    async fn verifies() -> Result<VerificationResult, VerificationError> {
+        Ok(VerificationResult {
+            is_valid: true,
+            ..Default::default()
+        })
+    }
+
+    const RISC0_VERSIONS: &[&str] = &["0.20.1", "1.0.5"];
+
+    fn select_risc0(selector: Option<&str>) -> Result<&'static str, VerificationError> {
+        select_version(config::VERIFY_TYPE_RISC0, RISC0_VERSIONS, selector, None)
+    }
+
+    #[test]
+    fn selects_versions() {
+        for (selector, version) in [
+            ("1.0", "1.0.5"),
+            ("1.x", "1.0.5"),
+            ("1", "1.0.5"),
+            ("1.0.5", "1.0.5"),
+            ("risc0@1.0", "1.0.5"),
+            ("risc0@1.x", "1.0.5"),
+            ("0.20", "0.20.1"),
+            ("0.x", "0.20.1"),
+            ("risc0@0.20.1", "0.20.1"),
+        ] {
+            assert_eq!(select_risc0(Some(selector)).unwrap(), version, "{selector}");
+        }
+    }
+
+    #[test]
+    fn defaults_to_the_configured_version() {
+        assert_eq!(select_risc0(None).unwrap(), "0.20.1");
+        let configured =
+            select_version(config::VERIFY_TYPE_RISC0, RISC0_VERSIONS, None, Some("1.x"));
+        assert_eq!(configured.unwrap(), "1.0.5");
+        let selected = select_version(
+            config::VERIFY_TYPE_RISC0,
+            RISC0_VERSIONS,
+            Some("0.20"),
+            Some("1.x"),
+        );
+        assert_eq!(selected.unwrap(), "0.20.1");
+    }
+
+    #[test]
+    fn rejects_unknown_versions() {
+        for selector in [
+            "2.0",
+            "1.1",
+            "0.2",
+            "0.20.2",
+            "",
+            "x",
+            "risc0@",
+            "risc0@2.x",
+        ] {
+            let err = select_risc0(Some(selector)).unwrap_err();
+            assert!(
+                matches!(err, VerificationError::VersionMismatch(_)),
+                "{selector}"
+            );
+        }
+        let configured =
+            select_version(config::VERIFY_TYPE_RISC0, RISC0_VERSIONS, None, Some("2.0"));
+        assert!(matches!(
+            configured.unwrap_err(),
+            VerificationError::VersionMismatch(_)
+        ));
+    }
+
+    #[test]
+    fn rejects_other_backends() {
+        let err = select_risc0(Some("sp1@1.0")).unwrap_err();
+        assert!(matches!(err, VerificationError::VersionMismatch(_)));
+    }
+
+    #[test]
+    fn needs_a_compiled_version() {
+        let err = select_version(config::VERIFY_TYPE_RISC0, &[], None, None).unwrap_err();
+        assert!(matches!(err, VerificationError::InvalidVerifyType(_)));
+        let err = select_version(config::VERIFY_TYPE_RISC0, &[], Some("1.0"), None).unwrap_err();
+        assert!(matches!(err, VerificationError::VersionMismatch(_)));
+    }
+
+    #[actix_web::test]
+    async fn isolates_panics() {
+        let err = isolate(panics_with_str()).await.unwrap_err();
+        assert!(matches!(&err, VerificationError::Panic(message) if message == "bad proof"));
+        let err = isolate(panics_with_string(7)).await.unwrap_err();
+        assert!(matches!(&err, VerificationError::Panic(message) if message == "bad proof 7"));
+        let err = isolate(panics_with_value()).await.unwrap_err();
+        assert!(matches!(&err, VerificationError::Panic(message) if message == "unknown panic"));
+    }
+
+    #[actix_web::test]
+    async fn keeps_verifying_after_a_panic() {
+        assert!(isolate(panics_with_str()).await.is_err());
+        assert!(isolate(verifies()).await.unwrap().is_valid);
+    }
+}
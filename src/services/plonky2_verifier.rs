@@ -0,0 +1,103 @@
+use log::{info, warn};
+use plonky2::field::goldilocks_field::GoldilocksField;
+use plonky2::field::types::PrimeField64;
+use plonky2::plonk::circuit_data::{
+    CommonCircuitData, VerifierCircuitData, VerifierOnlyCircuitData,
+};
+use plonky2::plonk::config::PoseidonGoldilocksConfig;
+use plonky2::plonk::proof::ProofWithPublicInputs;
+use plonky2::util::serialization::DefaultGateSerializer;
+
+use crate::config::{Config, VERIFY_TYPE_PLONKY2};
+use crate::errors::VerificationError;
+use crate::models::{Plonky2Circuit, Plonky2CircuitInfo, Plonky2Proof, VerificationResult};
+
+pub const VERSION: &str = env!("PLONKY2_VERSION");
+
+// the standard recursive configuration, Poseidon over Goldilocks with a
+// quadratic extension
+const D: usize = 2;
+type C = PoseidonGoldilocksConfig;
+type F = GoldilocksField;
+
+fn read_common_data(bytes: Vec<u8>) -> Result<CommonCircuitData<F, D>, VerificationError> {
+    CommonCircuitData::<F, D>::from_bytes(bytes, &DefaultGateSerializer).map_err(|err| {
+        VerificationError::KeyError(format!("Error deserializing common circuit data: {err:?}"))
+    })
+}
+
+fn read_verifier_data(bytes: Vec<u8>) -> Result<VerifierOnlyCircuitData<C, D>, VerificationError> {
+    VerifierOnlyCircuitData::<C, D>::from_bytes(bytes).map_err(|err| {
+        VerificationError::KeyError(format!("Error deserializing verifier data: {err:?}"))
+    })
+}
+
+fn registered_bytes(hex_bytes: &str) -> Result<Vec<u8>, VerificationError> {
+    hex::decode(hex_bytes).map_err(|err| {
+        VerificationError::KeyError(format!("Registered circuit data is not hex: {err}"))
+    })
+}
+
+// both files are checked once here, queued jobs carry their bytes into the
+// sandbox and the circuit digest pins the circuit in attestations
+pub fn register_circuit(circuit: &Plonky2Circuit) -> Result<Plonky2CircuitInfo, VerificationError> {
+    let envelope = circuit
+        .envelope
+        .unwrap_or(Config::init().envelope_for(VERIFY_TYPE_PLONKY2));
+    let common_bytes = envelope.read_file(&circuit.common_data_file_path)?;
+    let verifier_only_bytes = envelope.read_file(&circuit.verifier_data_file_path)?;
+    read_common_data(common_bytes.clone())?;
+    let verifier_only = read_verifier_data(verifier_only_bytes.clone())?;
+    let circuit_digest: Vec<u8> = verifier_only
+        .circuit_digest
+        .elements
+        .iter()
+        .flat_map(|element| element.to_canonical_u64().to_le_bytes())
+        .collect();
+    Ok(Plonky2CircuitInfo {
+        common_data: hex::encode(common_bytes),
+        verifier_data: hex::encode(verifier_only_bytes),
+        circuit_digest: hex::encode(circuit_digest),
+    })
+}
+
+pub async fn verify(data: &Plonky2Proof) -> Result<VerificationResult, VerificationError> {
+    info!("{:?}", data.proof_file_path);
+    let envelope = data
+        .envelope
+        .unwrap_or(Config::init().envelope_for(VERIFY_TYPE_PLONKY2));
+
+    let common = read_common_data(registered_bytes(&data.common_data)?)?;
+    let verifier_only = read_verifier_data(registered_bytes(&data.verifier_data)?)?;
+    let proof_bytes = envelope.read_file(&data.proof_file_path)?;
+    let proof =
+        ProofWithPublicInputs::<F, C, D>::from_bytes(proof_bytes, &common).map_err(|err| {
+            VerificationError::ProofError(format!("Error deserializing Plonky2 proof: {err:?}"))
+        })?;
+
+    let public_inputs = proof
+        .public_inputs
+        .iter()
+        .map(|input| input.to_canonical_u64().to_string())
+        .collect();
+    let verifier_data = VerifierCircuitData {
+        verifier_only,
+        common,
+    };
+    let verification_result = verifier_data.verify(proof);
+
+    match verification_result {
+        Ok(_) => Ok(VerificationResult {
+            is_valid: true,
+            public_inputs: Some(public_inputs),
+            ..Default::default()
+        }),
+        Err(err) => {
+            warn!("Verification failed: {:?}", err);
+            Ok(VerificationResult {
+                is_valid: false,
+                ..Default::default()
+            })
+        }
+    }
+}
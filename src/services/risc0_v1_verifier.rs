@@ -0,0 +1,108 @@
+use bincode::deserialize;
+use log::{info, warn};
+use risc0_zkvm_1::{InnerReceipt, Receipt, VerifierContext};
+
+use super::envelope::Envelope;
+use super::helpers::decode_proof_bytes;
+use crate::config::{Config, VERIFY_TYPE_RISC0};
+use crate::errors::VerificationError;
+use crate::models::{Risc0Proof, VerificationResult};
+
+// receipts from the 1.x line, served next to the 0.20 verifier
+pub const VERSION: &str = env!("RISC0_ZKVM_1_VERSION");
+
+pub fn compute_image_id(
+    elf_file_path: &str,
+    envelope: Envelope,
+) -> Result<[u32; 8], VerificationError> {
+    let elf = envelope.read_file(elf_file_path)?;
+    let digest = risc0_zkvm_1::compute_image_id(&elf)
+        .map_err(|err| VerificationError::ElfError(format!("Error loading guest ELF: {err:?}")))?;
+    let mut image_id: [u32; 8] = [0; 8];
+    image_id.copy_from_slice(digest.as_words());
+    Ok(image_id)
+}
+
+// 1.x renamed the receipt variants, the accepted kinds keep their 0.20 names
+fn receipt_kind(inner: &InnerReceipt) -> &'static str {
+    match inner {
+        InnerReceipt::Composite(_) => "composite",
+        InnerReceipt::Succinct(_) => "succinct",
+        InnerReceipt::Groth16(_) => "groth16",
+        InnerReceipt::Fake(_) => "fake",
+        _ => "unknown",
+    }
+}
+
+fn check_receipt(inner: &InnerReceipt, config: &Config) -> Result<(), VerificationError> {
+    let kind = receipt_kind(inner);
+    if !config
+        .risc0_receipt_kinds
+        .iter()
+        .any(|allowed| allowed == kind)
+    {
+        return Err(VerificationError::ProofError(format!(
+            "{kind} receipts are not accepted, expected one of {:?}",
+            config.risc0_receipt_kinds
+        )));
+    }
+    if let InnerReceipt::Succinct(succinct) = inner {
+        let control_id = hex::encode(succinct.control_id.as_bytes());
+        if !config.risc0_allowed_control_ids.is_empty()
+            && !config.risc0_allowed_control_ids.contains(&control_id)
+        {
+            return Err(VerificationError::ProofError(format!(
+                "succinct receipt control ID {control_id} is not allowed"
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn verifier_context(config: &Config) -> VerifierContext {
+    let mut context = VerifierContext::default();
+    if !config.risc0_hash_suites.is_empty() {
+        context
+            .suites
+            .retain(|name, _| config.risc0_hash_suites.contains(name));
+    }
+    context
+}
+
+pub async fn verify(data: &Risc0Proof) -> Result<VerificationResult, VerificationError> {
+    info!("{:?}", data);
+    let image_id = super::risc0_verifier::parse_image_id(&data.risc_zero_image_id)?;
+    let config = Config::init();
+    let envelope = data
+        .envelope
+        .unwrap_or(config.envelope_for(VERIFY_TYPE_RISC0));
+    let proof = envelope.read_file(&data.proof_file_path)?;
+
+    let (format, receipt_bytes) = decode_proof_bytes(proof)?;
+    info!("Reading RISC0 {} receipt as {:?}", VERSION, format);
+
+    let receipt: Receipt = deserialize(&receipt_bytes).map_err(|err| {
+        VerificationError::BincodeError(err, "Error deserializing receipt".to_string())
+    })?;
+
+    let kind = receipt_kind(&receipt.inner);
+    check_receipt(&receipt.inner, config)?;
+    info!("Verifying RISC0 {} {} receipt", VERSION, kind);
+    let verification_result = receipt.verify_with_context(&verifier_context(config), image_id);
+
+    match verification_result {
+        Ok(_) => Ok(VerificationResult {
+            is_valid: true,
+            proof_kind: Some(kind.to_string()),
+            ..Default::default()
+        }),
+        Err(err) => {
+            warn!("Verification failed: {:?}", err);
+            Ok(VerificationResult {
+                is_valid: false,
+                proof_kind: Some(kind.to_string()),
+                ..Default::default()
+            })
+        }
+    }
+}
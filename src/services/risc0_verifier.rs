@@ -1,15 +1,19 @@
 use bincode::deserialize;
 use log::{info, warn};
-use risc0_zkvm::Receipt;
-use serde_json::from_str;
+use risc0_zkvm::{
+    InnerReceipt, MemoryImage, Program, Receipt, VerifierContext, GUEST_MAX_MEM, PAGE_SIZE,
+};
+use std::collections::HashMap;
 
-use super::helpers::{handle_delete_files, handle_proof_bytes};
+use super::envelope::Envelope;
+use super::helpers::decode_proof_bytes;
+use crate::config::{Config, VERIFY_TYPE_RISC0};
 use crate::errors::VerificationError;
-use crate::models::{Proof, Risc0Proof, VerificationResult};
+use crate::models::{Risc0ProgramInfo, Risc0Proof, VerificationResult};
 
-pub async fn verify(data: &Risc0Proof) -> Result<VerificationResult, VerificationError> {
-    info!("{:?}", data);
-    let image_id_str = &data.risc_zero_image_id;
+pub const VERSION: &str = env!("RISC0_ZKVM_VERSION");
+
+pub fn parse_image_id(image_id_str: &str) -> Result<[u32; 8], VerificationError> {
     let numbers_str: Vec<&str> = image_id_str
         .trim_matches(|c| c == '[' || c == ']')
         .split(", ")
@@ -30,28 +34,152 @@ pub async fn verify(data: &Risc0Proof) -> Result<VerificationResult, Verificatio
             }
         }
     }
-    let proof = handle_proof_bytes(&data.proof_file_path)
-        .await
-        .map_err(|err| {
-            return err;
-        })?;
+    Ok(image_id)
+}
 
-    let receipt_bytes: Proof = from_str(&proof).map_err(|err| {
-        VerificationError::JSONError(err, "Error parsing receipt JSON".to_string())
+pub fn format_image_id(image_id: &[u32; 8]) -> String {
+    format!("{:?}", image_id)
+}
+
+pub fn compute_image_id(
+    elf_file_path: &str,
+    envelope: Envelope,
+) -> Result<[u32; 8], VerificationError> {
+    let elf = envelope.read_file(elf_file_path)?;
+    let program = Program::load_elf(&elf, GUEST_MAX_MEM as u32)
+        .map_err(|err| VerificationError::ElfError(format!("Error loading guest ELF: {err:?}")))?;
+    let image = MemoryImage::new(&program, PAGE_SIZE as u32).map_err(|err| {
+        VerificationError::ElfError(format!("Error building memory image: {err:?}"))
     })?;
+    let mut image_id: [u32; 8] = [0; 8];
+    image_id.copy_from_slice(image.compute_id().as_words());
+    Ok(image_id)
+}
+
+// a registered program id wins, and a claimed image id must agree with it.
+// The version the program was registered for comes along with its image id
+pub fn resolve_image_id(
+    programs: &HashMap<String, Risc0ProgramInfo>,
+    program_id: Option<&String>,
+    claimed_image_id: Option<&String>,
+    registered_only: bool,
+) -> Result<([u32; 8], Option<String>), VerificationError> {
+    let claimed = match claimed_image_id {
+        Some(image_id_str) => Some(parse_image_id(image_id_str)?),
+        None => None,
+    };
+    match program_id {
+        Some(program_id) => {
+            let registered = programs.get(program_id).ok_or_else(|| {
+                VerificationError::InvalidImageID(format!("Unknown RISC0 program {program_id}"))
+            })?;
+            match claimed {
+                Some(claimed) if claimed != registered.image_id => {
+                    Err(VerificationError::InvalidImageID(
+                        "Claimed image ID does not match registered program".to_string(),
+                    ))
+                }
+                _ => Ok((registered.image_id, Some(registered.version.clone()))),
+            }
+        }
+        None => {
+            let claimed = claimed.ok_or_else(|| {
+                VerificationError::InvalidImageID("Missing image ID or program ID".to_string())
+            })?;
+            if registered_only
+                && !programs
+                    .values()
+                    .any(|registered| registered.image_id == claimed)
+            {
+                return Err(VerificationError::InvalidImageID(
+                    "Claimed image ID does not match any registered program".to_string(),
+                ));
+            }
+            Ok((claimed, None))
+        }
+    }
+}
+
+// segment receipts of a whole session, a single recursion receipt, or the
+// recursion receipt wrapped in a Groth16 proof
+fn receipt_kind(inner: &InnerReceipt) -> &'static str {
+    match inner {
+        InnerReceipt::Flat(_) => "composite",
+        InnerReceipt::Succinct(_) => "succinct",
+        InnerReceipt::Compact(_) => "groth16",
+        InnerReceipt::Fake => "fake",
+    }
+}
+
+fn check_receipt(inner: &InnerReceipt, config: &Config) -> Result<(), VerificationError> {
+    let kind = receipt_kind(inner);
+    if !config
+        .risc0_receipt_kinds
+        .iter()
+        .any(|allowed| allowed == kind)
+    {
+        return Err(VerificationError::ProofError(format!(
+            "{kind} receipts are not accepted, expected one of {:?}",
+            config.risc0_receipt_kinds
+        )));
+    }
+    if let InnerReceipt::Succinct(succinct) = inner {
+        let control_id = hex::encode(succinct.control_id.as_bytes());
+        if !config.risc0_allowed_control_ids.is_empty()
+            && !config.risc0_allowed_control_ids.contains(&control_id)
+        {
+            return Err(VerificationError::ProofError(format!(
+                "succinct receipt control ID {control_id} is not allowed"
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn verifier_context(config: &Config) -> VerifierContext {
+    let mut context = VerifierContext::default();
+    if !config.risc0_hash_suites.is_empty() {
+        context
+            .suites
+            .retain(|name, _| config.risc0_hash_suites.contains(name));
+    }
+    context
+}
+
+pub async fn verify(data: &Risc0Proof) -> Result<VerificationResult, VerificationError> {
+    info!("{:?}", data);
+    let image_id = parse_image_id(&data.risc_zero_image_id)?;
+    let config = Config::init();
+    let envelope = data
+        .envelope
+        .unwrap_or(config.envelope_for(VERIFY_TYPE_RISC0));
+    let proof = envelope.read_file(&data.proof_file_path)?;
+
+    let (format, receipt_bytes) = decode_proof_bytes(proof)?;
+    info!("Reading RISC0 receipt as {:?}", format);
 
-    let receipt: Receipt = deserialize(&receipt_bytes.proof).map_err(|err| {
+    let receipt: Receipt = deserialize(&receipt_bytes).map_err(|err| {
         VerificationError::BincodeError(err, "Error deserializing receipt".to_string())
     })?;
 
-    let verification_result = receipt.verify(image_id);
+    let kind = receipt_kind(&receipt.inner);
+    check_receipt(&receipt.inner, config)?;
+    info!("Verifying RISC0 {} receipt", kind);
+    let verification_result = receipt.verify_with_context(&verifier_context(config), image_id);
 
-    handle_delete_files(&vec![&data.proof_file_path]);
     match verification_result {
-        Ok(_) => Ok(VerificationResult { is_valid: true }),
+        Ok(_) => Ok(VerificationResult {
+            is_valid: true,
+            proof_kind: Some(kind.to_string()),
+            ..Default::default()
+        }),
         Err(err) => {
             warn!("Verification failed: {:?}", err);
-            Ok(VerificationResult { is_valid: false })
+            Ok(VerificationResult {
+                is_valid: false,
+                proof_kind: Some(kind.to_string()),
+                ..Default::default()
+            })
         }
     }
 }
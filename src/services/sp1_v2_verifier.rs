@@ -0,0 +1,79 @@
+use bincode::deserialize;
+use log::{info, warn};
+use serde_json::from_slice;
+use sp1_sdk::{ProverClient, SP1Proof, SP1ProofWithPublicValues, SP1VerificationError};
+
+use super::helpers::is_json_object;
+use crate::config::{Config, VERIFY_TYPE_SP1};
+use crate::errors::VerificationError;
+use crate::models::{Sp1Proof, VerificationResult};
+
+// proofs written by sp1-sdk 2.x, served next to the pinned sp1-core verifier.
+// Unlike sp1-core it verifies every variant, core, compressed and the plonk
+// and groth16 wrapped proofs, the wrapped ones against the circuit artifacts
+// sp1-sdk installs on first use
+pub const VERSION: &str = env!("SP1_SDK_VERSION");
+
+fn proof_kind(proof: &SP1Proof) -> &'static str {
+    match proof {
+        SP1Proof::Core(_) => "core",
+        SP1Proof::Compressed(_) => "compressed",
+        SP1Proof::Plonk(_) => "plonk",
+        SP1Proof::Groth16(_) => "groth16",
+    }
+}
+
+// SP1ProofWithPublicValues::save writes bincode, the JSON encoding of the
+// same struct is accepted as well
+fn parse_proof(proof: &[u8]) -> Result<SP1ProofWithPublicValues, VerificationError> {
+    if is_json_object(proof) {
+        return from_slice(proof).map_err(|err| {
+            VerificationError::JSONError(
+                err,
+                format!("Proof JSON is not an sp1-sdk {VERSION} proof"),
+            )
+        });
+    }
+    deserialize(proof).map_err(|err| {
+        VerificationError::BincodeError(err, format!("Proof is not an sp1-sdk {VERSION} proof"))
+    })
+}
+
+pub async fn verify(data: &Sp1Proof) -> Result<VerificationResult, VerificationError> {
+    info!("{:?}", data);
+    let envelope = data
+        .envelope
+        .unwrap_or(Config::init().envelope_for(VERIFY_TYPE_SP1));
+
+    let proof = parse_proof(&envelope.read_file(&data.proof_file_path)?)?;
+    let kind = proof_kind(&proof.proof);
+
+    let elf = envelope.read_file(&data.elf_file_path)?;
+    let client = ProverClient::local();
+    let (_, vk) = client.setup(&elf);
+    info!(
+        "Verifying sp1-sdk {} {} proof from {}",
+        VERSION, kind, proof.sp1_version
+    );
+    match client.verify(&proof, &vk) {
+        Ok(()) => Ok(VerificationResult {
+            is_valid: true,
+            proof_kind: Some(kind.to_string()),
+            ..Default::default()
+        }),
+        Err(SP1VerificationError::VersionMismatch(version)) => {
+            Err(VerificationError::VersionMismatch(format!(
+                "{kind} proof was produced by sp1 {version}, sp1@{VERSION} only verifies proofs \
+                 of its own circuit version"
+            )))
+        }
+        Err(err) => {
+            warn!("Verification failed: {:?}", err);
+            Ok(VerificationResult {
+                is_valid: false,
+                proof_kind: Some(kind.to_string()),
+                ..Default::default()
+            })
+        }
+    }
+}
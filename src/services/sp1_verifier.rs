@@ -1,36 +1,108 @@
+use bincode::deserialize;
 use log::{info, warn};
-use serde_json::from_str;
+use serde_json::from_slice;
+use sp1_core::utils::BabyBearBlake3;
 use sp1_core::{SP1ProofWithIO, SP1Verifier};
-use std::fs;
 
-use super::helpers::{handle_delete_files, handle_proof_bytes};
+use super::helpers::is_json_object;
+use super::versions;
+use crate::config::{Config, VERIFY_TYPE_SP1};
 use crate::errors::VerificationError;
 use crate::models::{Sp1Proof, VerificationResult};
 
-pub async fn verify(data: &Sp1Proof) -> Result<VerificationResult, VerificationError> {
-    info!("{:?}", data);
+pub const VERSION: &str = env!("SP1_CORE_VERSION");
+
+// proof variants of sp1-sdk's SP1Proof. The pinned sp1-core revision only
+// has a verifier for core proofs, the others are recognised so they can be
+// rejected with a clear error. sp1-sdk proofs are verified by the sp1-v2
+// version
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sp1ProofKind {
+    Core,
+    Compressed,
+    Plonk,
+    Groth16,
+}
+
+impl Sp1ProofKind {
+    fn from_sdk_variant(variant: &str) -> Option<Self> {
+        match variant {
+            "Core" => Some(Sp1ProofKind::Core),
+            "Compressed" => Some(Sp1ProofKind::Compressed),
+            "Plonk" => Some(Sp1ProofKind::Plonk),
+            "Groth16" => Some(Sp1ProofKind::Groth16),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Sp1ProofKind::Core => "core",
+            Sp1ProofKind::Compressed => "compressed",
+            Sp1ProofKind::Plonk => "plonk",
+            Sp1ProofKind::Groth16 => "groth16",
+        }
+    }
+}
 
-    let proof = handle_proof_bytes(&data.proof_file_path)
-        .await
-        .map_err(|err| {
-            return err;
+// sp1-sdk's SP1ProofWithPublicValues records the producing release in its
+// sp1_version field and the variant as the key of its proof object, e.g.
+// {"proof": {"Compressed": ...}, "public_values": ..., "sp1_version": "v1.1.0"}
+fn sdk_proof_error(value: &serde_json::Value) -> Option<VerificationError> {
+    let version = value.get("sp1_version")?.as_str()?;
+    let variant = value.get("proof")?.as_object()?.keys().next()?.clone();
+    let kind = match Sp1ProofKind::from_sdk_variant(&variant) {
+        Some(kind) => kind.name().to_string(),
+        None => variant,
+    };
+    Some(VerificationError::VersionMismatch(format!(
+        "{kind} proof was produced by sp1-sdk {version}, sp1@{VERSION} only verifies \
+         SP1ProofWithIO core proofs, available versions are {:?}",
+        versions(VERIFY_TYPE_SP1)
+    )))
+}
+
+fn parse_proof(proof: &[u8]) -> Result<SP1ProofWithIO<BabyBearBlake3>, VerificationError> {
+    if is_json_object(proof) {
+        let value: serde_json::Value = from_slice(proof).map_err(|err| {
+            VerificationError::JSONError(err, "Error parsing proof JSON".to_string())
         })?;
+        if let Some(err) = sdk_proof_error(&value) {
+            return Err(err);
+        }
+        return serde_json::from_value(value).map_err(|err| {
+            VerificationError::JSONError(err, "Proof JSON is not an SP1 core proof".to_string())
+        });
+    }
+    deserialize(proof).map_err(|err| {
+        VerificationError::BincodeError(err, "Proof is not an SP1 core proof".to_string())
+    })
+}
 
-    let parsed_proof: SP1ProofWithIO<sp1_core::utils::BabyBearBlake3> = from_str(&proof)
-        .map_err(|err| VerificationError::JSONError(err, "Error parsing proof JSON".to_string()))?;
+pub async fn verify(data: &Sp1Proof) -> Result<VerificationResult, VerificationError> {
+    info!("{:?}", data);
+    let envelope = data
+        .envelope
+        .unwrap_or(Config::init().envelope_for(VERIFY_TYPE_SP1));
 
-    let elf = fs::read(&data.elf_file_path)
-        .map_err(|err| VerificationError::IOError(err, "Error reading ELF file".to_string()))?;
-    let sliced_elf = &elf[32..];
-    let verification_result = SP1Verifier::verify(&sliced_elf, &parsed_proof);
+    let proof = envelope.read_file(&data.proof_file_path)?;
+    let parsed_proof = parse_proof(&proof)?;
 
-    handle_delete_files(&vec![&data.proof_file_path, &data.elf_file_path]);
+    let elf = envelope.read_file(&data.elf_file_path)?;
+    let verification_result = SP1Verifier::verify(&elf, &parsed_proof);
 
     match verification_result {
-        Ok(_) => Ok(VerificationResult { is_valid: true }),
+        Ok(_) => Ok(VerificationResult {
+            is_valid: true,
+            proof_kind: Some(Sp1ProofKind::Core.name().to_string()),
+            ..Default::default()
+        }),
         Err(err) => {
             warn!("Verification failed: {:?}", err);
-            Ok(VerificationResult { is_valid: false })
+            Ok(VerificationResult {
+                is_valid: false,
+                ..Default::default()
+            })
         }
     }
 }
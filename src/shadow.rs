@@ -0,0 +1,153 @@
+use log::{info, warn};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::TempDir;
+use tokio::sync::Mutex;
+
+use crate::config::{backend_name, Config};
+use crate::models::{ShadowStats, VerificationJob};
+use crate::sandbox;
+use crate::services;
+
+// SHADOW_VERSIONS entries need a second version of the backend compiled in,
+// which risc0 has with risc0-v1 and sp1 with sp1-v2. Checked once at startup so
+// the other entries do not warn on every proof
+pub fn check_config(config: &Config) {
+    for (backend, selector) in &config.shadow_versions {
+        let compiled = match services::backends()
+            .into_iter()
+            .find(|compiled| compiled.name == *backend)
+        {
+            Some(compiled) => compiled,
+            None => {
+                warn!("SHADOW_VERSIONS names {backend}, which is not compiled in");
+                continue;
+            }
+        };
+        if compiled.versions.len() < 2 {
+            warn!(
+                "SHADOW_VERSIONS names {backend}@{selector}, but {backend} is built with a single \
+                 version, its proofs are not shadowed"
+            );
+        } else if let Err(err) = services::resolve_version(compiled.verify_type, Some(selector)) {
+            warn!("SHADOW_VERSIONS {backend}@{selector}: {:?}", err);
+        }
+    }
+}
+
+// the candidate version configured for the job's backend, None when there is
+// nothing to compare against
+fn candidate(job: &VerificationJob, config: &Config) -> Option<(&'static str, &'static str)> {
+    let verify_type = job.verify_type();
+    let selector = config.shadow_versions.get(backend_name(verify_type))?;
+    let candidate = services::resolve_version(verify_type, Some(selector)).ok()?;
+    let active = services::resolve_version(verify_type, job.version()).ok()?;
+    (active != candidate).then_some((active, candidate))
+}
+
+fn copy_files(job: &VerificationJob, shadow_dir: &Path) -> std::io::Result<Vec<(String, String)>> {
+    job.files()
+        .into_iter()
+        .enumerate()
+        .map(|(index, file)| {
+            let file_name = Path::new(&file)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| "file".to_string());
+            let copy = shadow_dir.join(format!("{}-{}", index, file_name));
+            fs::copy(&file, &copy)?;
+            Ok((file, copy.to_string_lossy().to_string()))
+        })
+        .collect()
+}
+
+// verifies a copy of the job's files with the candidate version in the
+// background and records whether it agreed with the active result, which is
+// the only one reported. Called before the files are released
+pub fn spawn_compare(
+    tx_id: &str,
+    job: &VerificationJob,
+    active_valid: bool,
+    sandboxed: bool,
+    shadow_stats: &Arc<Mutex<HashMap<String, ShadowStats>>>,
+) {
+    let config = Config::init();
+    let (active, candidate) = match candidate(job, config) {
+        Some(versions) => versions,
+        None => return,
+    };
+    // a directory of its own per job, removed when the comparison is done
+    let shadow_dir = match TempDir::with_prefix("jugalbandi-shadow-") {
+        Ok(shadow_dir) => shadow_dir,
+        Err(err) => {
+            warn!(
+                "Error creating a directory for the shadow verification of {}: {:?}",
+                tx_id, err
+            );
+            return;
+        }
+    };
+    let candidate_job = match copy_files(job, shadow_dir.path()) {
+        Ok(copies) => job
+            .relocated(&copies)
+            .and_then(|job| job.with_version(candidate)),
+        Err(err) => {
+            warn!(
+                "Error copying {} for its shadow verification: {:?}",
+                tx_id, err
+            );
+            None
+        }
+    };
+    let candidate_job = match candidate_job {
+        Some(candidate_job) => candidate_job,
+        None => return,
+    };
+
+    let tx_id = tx_id.to_string();
+    let backend = backend_name(job.verify_type());
+    let shadow_stats = shadow_stats.clone();
+    tokio::spawn(async move {
+        let candidate_result = if sandboxed {
+            sandbox::verify_sandboxed(candidate_job).await
+        } else {
+            services::verify_isolated(candidate_job).await
+        };
+        let shadow_path = shadow_dir.path().to_path_buf();
+        if let Err(err) = shadow_dir.close() {
+            warn!("Error removing {}: {:?}", shadow_path.display(), err);
+        }
+
+        let candidate_valid = matches!(&candidate_result, Ok(result) if result.is_valid);
+        let mut shadow_stats = shadow_stats.lock().await;
+        let stats = shadow_stats
+            .entry(format!("{backend}@{active}"))
+            .or_insert_with(|| ShadowStats {
+                active_version: active.to_string(),
+                candidate_version: candidate.to_string(),
+                ..Default::default()
+            });
+        stats.candidate_version = candidate.to_string();
+        stats.compared += 1;
+        if let Err(err) = &candidate_result {
+            stats.candidate_errors += 1;
+            warn!("Shadow {}@{} error: {:?}", backend, candidate, err);
+        }
+        if active_valid == candidate_valid {
+            stats.agreed += 1;
+            info!(
+                "Shadow {}@{} agrees with {}@{}",
+                backend, candidate, backend, active
+            );
+        } else {
+            stats.disagreed += 1;
+            stats.last_disagreement = Some(tx_id.clone());
+            warn!(
+                "Shadow disagreement on {}: {}@{} is_valid={} but {}@{} is_valid={}",
+                tx_id, backend, active, active_valid, backend, candidate, candidate_valid
+            );
+        }
+    });
+}
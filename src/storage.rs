@@ -1,23 +1,243 @@
-use crate::models::{MidenProof, Risc0Proof, Sp1Proof, VerifyProof, JoltProof};
+use crate::audit::AuditLog;
+use crate::batch::BatchStore;
+use crate::config::{self, Config};
+#[cfg(feature = "jolt")]
+use crate::models::JoltProof;
+#[cfg(feature = "sp1")]
+use crate::models::Sp1Proof;
+#[cfg(feature = "groth16")]
+use crate::models::{Groth16KeyInfo, Groth16Proof};
+#[cfg(feature = "halo2")]
+use crate::models::{Halo2KeysInfo, Halo2Proof};
+#[cfg(feature = "miden")]
+use crate::models::{MidenProgramInfo, MidenProof};
+#[cfg(feature = "plonky2")]
+use crate::models::{Plonky2CircuitInfo, Plonky2Proof};
+#[cfg(feature = "risc0")]
+use crate::models::{Risc0ProgramInfo, Risc0Proof};
+use crate::models::{ShadowStats, SweepReport, VerificationJob, VerificationStatus, VerifyProof};
+use crate::quorum::PeerJob;
 use lazy_static::lazy_static;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+// lazy_static only forwards attributes to the generated type, so each
+// backend's statics get their own gated block
+#[cfg(feature = "sp1")]
 lazy_static! {
     pub static ref SP1_HASHMAP: Arc<Mutex<HashMap<String, Sp1Proof>>> =
         Arc::new(Mutex::new(HashMap::new()));
+}
+
+#[cfg(feature = "miden")]
+lazy_static! {
     pub static ref MIDEN_HASHMAP: Arc<Mutex<HashMap<String, MidenProof>>> =
         Arc::new(Mutex::new(HashMap::new()));
+    pub static ref MIDEN_PROGRAMS: Arc<Mutex<HashMap<String, MidenProgramInfo>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+}
+
+#[cfg(feature = "risc0")]
+lazy_static! {
     pub static ref RISC0_HASHMAP: Arc<Mutex<HashMap<String, Risc0Proof>>> =
         Arc::new(Mutex::new(HashMap::new()));
+    pub static ref RISC0_PROGRAMS: Arc<Mutex<HashMap<String, Risc0ProgramInfo>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+}
+
+#[cfg(feature = "jolt")]
+lazy_static! {
     pub static ref JOLT_HASHMAP: Arc<Mutex<HashMap<String, JoltProof>>> =
         Arc::new(Mutex::new(HashMap::new()));
+}
+
+#[cfg(feature = "groth16")]
+lazy_static! {
+    pub static ref GROTH16_HASHMAP: Arc<Mutex<HashMap<String, Groth16Proof>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    pub static ref GROTH16_KEYS: Arc<Mutex<HashMap<String, Groth16KeyInfo>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+}
+
+#[cfg(feature = "plonky2")]
+lazy_static! {
+    pub static ref PLONKY2_HASHMAP: Arc<Mutex<HashMap<String, Plonky2Proof>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    pub static ref PLONKY2_CIRCUITS: Arc<Mutex<HashMap<String, Plonky2CircuitInfo>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+}
+
+#[cfg(feature = "halo2")]
+lazy_static! {
+    pub static ref HALO2_HASHMAP: Arc<Mutex<HashMap<String, Halo2Proof>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    pub static ref HALO2_KEYS: Arc<Mutex<HashMap<String, Halo2KeysInfo>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+}
+
+lazy_static! {
     pub static ref VERIFY_QUEUE: Arc<Mutex<VecDeque<VerifyProof>>> =
         Arc::new(Mutex::new(VecDeque::new()));
+    // jobs popped from VERIFY_QUEUE whose results are not recorded yet, by tx_id
+    pub static ref IN_FLIGHT: Arc<Mutex<HashMap<String, VerifyProof>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    pub static ref PEER_QUEUE: Arc<Mutex<VecDeque<PeerJob>>> =
+        Arc::new(Mutex::new(VecDeque::new()));
     pub static ref INSTANTIATED_PORTS: Arc<Mutex<Vec<u16>>> =
         Arc::new(Mutex::new(Vec::from(vec![8081, 8082, 8083, 8084, 8085])));
     pub static ref UNINSTANTIATED_PORTS: Arc<Mutex<Vec<u16>>> =
         Arc::new(Mutex::new(Vec::from(vec![8086, 8087, 8088, 8089, 8090])));
+    pub static ref LAST_SWEEP_REPORT: Arc<Mutex<Option<SweepReport>>> = Arc::new(Mutex::new(None));
+    pub static ref SHADOW_STATS: Arc<Mutex<HashMap<String, ShadowStats>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    pub static ref BATCH_STORE: Arc<Mutex<BatchStore>> =
+        Arc::new(Mutex::new(BatchStore::open(&Config::init().batch_dir)));
+    // opened once, main refuses to start when the chain is broken
+    pub static ref AUDIT_LOG: Result<Arc<Mutex<AuditLog>>, String> =
+        AuditLog::open(&Config::init().audit_log_file)
+            .map(|audit_log| Arc::new(Mutex::new(audit_log)))
+            .map_err(|err| err.to_string());
+    pub static ref VERIFICATION_STATUSES: Arc<Mutex<HashMap<String, VerificationStatus>>> =
+        Arc::new(Mutex::new(HashMap::new()));
     pub static ref PORT_INDEX: Arc<Mutex<usize>> = Arc::new(Mutex::new(0));
+    // resolved once, main refuses to start when one of the roots is missing
+    pub static ref SPOOL_ROOTS: Result<Vec<PathBuf>, String> =
+        Config::init().canonical_spool_roots();
+}
+
+// where finished verifications are recorded, shared by the worker and the
+// tasks that finish quorum jobs
+#[derive(Clone)]
+pub struct ResultStores {
+    pub statuses: Arc<Mutex<HashMap<String, VerificationStatus>>>,
+    pub batch_store: Arc<Mutex<BatchStore>>,
+    pub audit_log: Arc<Mutex<AuditLog>>,
+}
+
+// the proof maps of the compiled in backends, shared by the routes that queue
+// proofs and the worker that verifies them
+#[derive(Clone)]
+pub struct ProofStores {
+    #[cfg(feature = "sp1")]
+    pub sp1: Arc<Mutex<HashMap<String, Sp1Proof>>>,
+    #[cfg(feature = "miden")]
+    pub miden: Arc<Mutex<HashMap<String, MidenProof>>>,
+    #[cfg(feature = "risc0")]
+    pub risc0: Arc<Mutex<HashMap<String, Risc0Proof>>>,
+    #[cfg(feature = "jolt")]
+    pub jolt: Arc<Mutex<HashMap<String, JoltProof>>>,
+    #[cfg(feature = "groth16")]
+    pub groth16: Arc<Mutex<HashMap<String, Groth16Proof>>>,
+    #[cfg(feature = "plonky2")]
+    pub plonky2: Arc<Mutex<HashMap<String, Plonky2Proof>>>,
+    #[cfg(feature = "halo2")]
+    pub halo2: Arc<Mutex<HashMap<String, Halo2Proof>>>,
+}
+
+impl ProofStores {
+    pub fn shared() -> Self {
+        ProofStores {
+            #[cfg(feature = "sp1")]
+            sp1: SP1_HASHMAP.clone(),
+            #[cfg(feature = "miden")]
+            miden: MIDEN_HASHMAP.clone(),
+            #[cfg(feature = "risc0")]
+            risc0: RISC0_HASHMAP.clone(),
+            #[cfg(feature = "jolt")]
+            jolt: JOLT_HASHMAP.clone(),
+            #[cfg(feature = "groth16")]
+            groth16: GROTH16_HASHMAP.clone(),
+            #[cfg(feature = "plonky2")]
+            plonky2: PLONKY2_HASHMAP.clone(),
+            #[cfg(feature = "halo2")]
+            halo2: HALO2_HASHMAP.clone(),
+        }
+    }
+
+    // files of the jobs waiting in the queue or in flight. The worker moves a
+    // job into in_flight before it lets go of the queue, so reading the queue
+    // first never misses one
+    pub async fn queued_files(
+        &self,
+        queue: &Mutex<VecDeque<VerifyProof>>,
+        in_flight: &Mutex<HashMap<String, VerifyProof>>,
+    ) -> HashSet<PathBuf> {
+        let mut queued: Vec<VerifyProof> = queue.lock().await.iter().cloned().collect();
+        queued.extend(in_flight.lock().await.values().cloned());
+        let mut files = HashSet::new();
+        for verify_proof in queued {
+            if let Some(job) = self
+                .job(verify_proof.verify_type, &verify_proof.tx_id)
+                .await
+            {
+                files.extend(job.files().into_iter().map(PathBuf::from));
+            }
+        }
+        files
+    }
+
+    // the proof submitted under tx_id for the given backend
+    pub async fn job(&self, verify_type: u8, tx_id: &str) -> Option<VerificationJob> {
+        match verify_type {
+            #[cfg(feature = "sp1")]
+            config::VERIFY_TYPE_SP1 => self
+                .sp1
+                .lock()
+                .await
+                .get(tx_id)
+                .cloned()
+                .map(VerificationJob::Sp1),
+            #[cfg(feature = "miden")]
+            config::VERIFY_TYPE_MIDEN => self
+                .miden
+                .lock()
+                .await
+                .get(tx_id)
+                .cloned()
+                .map(VerificationJob::Miden),
+            #[cfg(feature = "risc0")]
+            config::VERIFY_TYPE_RISC0 => self
+                .risc0
+                .lock()
+                .await
+                .get(tx_id)
+                .cloned()
+                .map(VerificationJob::Risc0),
+            #[cfg(feature = "jolt")]
+            config::VERIFY_TYPE_JOLT => self
+                .jolt
+                .lock()
+                .await
+                .get(tx_id)
+                .cloned()
+                .map(VerificationJob::Jolt),
+            #[cfg(feature = "groth16")]
+            config::VERIFY_TYPE_GROTH16 => self
+                .groth16
+                .lock()
+                .await
+                .get(tx_id)
+                .cloned()
+                .map(VerificationJob::Groth16),
+            #[cfg(feature = "plonky2")]
+            config::VERIFY_TYPE_PLONKY2 => self
+                .plonky2
+                .lock()
+                .await
+                .get(tx_id)
+                .cloned()
+                .map(VerificationJob::Plonky2),
+            #[cfg(feature = "halo2")]
+            config::VERIFY_TYPE_HALO2 => self
+                .halo2
+                .lock()
+                .await
+                .get(tx_id)
+                .cloned()
+                .map(VerificationJob::Halo2),
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,212 @@
+#!/usr/bin/env python3
+# Writes the Groth16 known-answer fixtures used by groth16_verifier's tests.
+#
+# The proof is simulated from a fixed trapdoor (alpha, beta, gamma, delta and
+# the IC scalars are known), so it satisfies the Groth16 pairing equation for
+# the public inputs below without needing a circuit or a prover. The key and
+# proof are written in the snarkjs JSON layout and in gnark's binary layout,
+# with a compressed gnark key and a raw (uncompressed) gnark proof.
+import json
+import os
+
+P = 21888242871839275222246405745257275088696311157297823662689037894645226208583
+R = 21888242871839275222246405745257275088548364400416034343698204186575808495617
+
+G1 = (1, 2)
+G2 = (
+    (
+        10857046999023057135944570762232829481370756359578518086990519993285655852781,
+        11559732032986387107991004021392285783925812861821192530917403151452391805634,
+    ),
+    (
+        8495653923123431417604973247489272438418190587263600148770280649306958101930,
+        4082367875863433681332203403145435568316851327593401208105741076214120093531,
+    ),
+)
+
+ALPHA, BETA, GAMMA, DELTA = 7, 11, 13, 17
+IC = [19, 23, 29]
+PUBLIC_INPUTS = [33, 3]
+A, B = 31, 37
+
+
+class Fq:
+    zero = 0
+    one = 1
+
+    @staticmethod
+    def add(x, y):
+        return (x + y) % P
+
+    @staticmethod
+    def sub(x, y):
+        return (x - y) % P
+
+    @staticmethod
+    def mul(x, y):
+        return (x * y) % P
+
+    @staticmethod
+    def inv(x):
+        return pow(x, P - 2, P)
+
+
+class Fq2:
+    zero = (0, 0)
+    one = (1, 0)
+
+    @staticmethod
+    def add(x, y):
+        return ((x[0] + y[0]) % P, (x[1] + y[1]) % P)
+
+    @staticmethod
+    def sub(x, y):
+        return ((x[0] - y[0]) % P, (x[1] - y[1]) % P)
+
+    @staticmethod
+    def mul(x, y):
+        # u^2 = -1
+        return (
+            (x[0] * y[0] - x[1] * y[1]) % P,
+            (x[0] * y[1] + x[1] * y[0]) % P,
+        )
+
+    @staticmethod
+    def inv(x):
+        norm = pow((x[0] * x[0] + x[1] * x[1]) % P, P - 2, P)
+        return ((x[0] * norm) % P, (-x[1] * norm) % P)
+
+
+def point_add(field, p1, p2):
+    if p1 is None:
+        return p2
+    if p2 is None:
+        return p1
+    (x1, y1), (x2, y2) = p1, p2
+    if x1 == x2:
+        if field.add(y1, y2) == field.zero:
+            return None
+        slope = field.mul(field.mul(field.mul(x1, x1), three(field)), field.inv(field.add(y1, y1)))
+    else:
+        slope = field.mul(field.sub(y2, y1), field.inv(field.sub(x2, x1)))
+    x3 = field.sub(field.sub(field.mul(slope, slope), x1), x2)
+    y3 = field.sub(field.mul(slope, field.sub(x1, x3)), y1)
+    return (x3, y3)
+
+
+def three(field):
+    return 3 if field is Fq else (3, 0)
+
+
+def scalar_mul(field, point, scalar):
+    result = None
+    scalar %= R
+    while scalar:
+        if scalar & 1:
+            result = point_add(field, result, point)
+        point = point_add(field, point, point)
+        scalar >>= 1
+    return result
+
+
+def g1(scalar):
+    return scalar_mul(Fq, G1, scalar)
+
+
+def g2(scalar):
+    return scalar_mul(Fq2, G2, scalar)
+
+
+def snarkjs_g1(point):
+    return [str(point[0]), str(point[1]), "1"]
+
+
+def snarkjs_g2(point):
+    return [[str(c) for c in point[0]], [str(c) for c in point[1]], ["1", "0"]]
+
+
+def largest(y):
+    return y > (P - 1) // 2
+
+
+def largest2(y):
+    return largest(y[1]) if y[0] == 0 else largest(y[0])
+
+
+def be(value):
+    return value.to_bytes(32, "big")
+
+
+def gnark_g1_compressed(point):
+    encoded = bytearray(be(point[0]))
+    encoded[0] |= 0b11 << 6 if largest(point[1]) else 0b10 << 6
+    return bytes(encoded)
+
+
+def gnark_g2_compressed(point):
+    encoded = bytearray(be(point[0][1]) + be(point[0][0]))
+    encoded[0] |= 0b11 << 6 if largest2(point[1]) else 0b10 << 6
+    return bytes(encoded)
+
+
+def gnark_g1_raw(point):
+    return be(point[0]) + be(point[1])
+
+
+def gnark_g2_raw(point):
+    return be(point[0][1]) + be(point[0][0]) + be(point[1][1]) + be(point[1][0])
+
+
+def main():
+    out = os.path.dirname(os.path.abspath(__file__))
+    linear = IC[0] + sum(x * ic for x, ic in zip(PUBLIC_INPUTS, IC[1:]))
+    c = (A * B - ALPHA * BETA - GAMMA * linear) * pow(DELTA, R - 2, R) % R
+    proof = (g1(A), g2(B), g1(c))
+    ic_points = [g1(scalar) for scalar in IC]
+
+    vk_json = {
+        "protocol": "groth16",
+        "curve": "bn128",
+        "nPublic": len(PUBLIC_INPUTS),
+        "vk_alpha_1": snarkjs_g1(g1(ALPHA)),
+        "vk_beta_2": snarkjs_g2(g2(BETA)),
+        "vk_gamma_2": snarkjs_g2(g2(GAMMA)),
+        "vk_delta_2": snarkjs_g2(g2(DELTA)),
+        "IC": [snarkjs_g1(point) for point in ic_points],
+    }
+    proof_json = {
+        "pi_a": snarkjs_g1(proof[0]),
+        "pi_b": snarkjs_g2(proof[1]),
+        "pi_c": snarkjs_g1(proof[2]),
+        "protocol": "groth16",
+        "curve": "bn128",
+    }
+    with open(os.path.join(out, "snarkjs_vk.json"), "w") as f:
+        json.dump(vk_json, f, indent=1)
+        f.write("\n")
+    with open(os.path.join(out, "snarkjs_proof.json"), "w") as f:
+        json.dump(proof_json, f, indent=1)
+        f.write("\n")
+    with open(os.path.join(out, "public.json"), "w") as f:
+        json.dump([str(x) for x in PUBLIC_INPUTS], f)
+        f.write("\n")
+
+    vk_bin = (
+        gnark_g1_compressed(g1(ALPHA))
+        + gnark_g1_compressed(g1(BETA))
+        + gnark_g2_compressed(g2(BETA))
+        + gnark_g2_compressed(g2(GAMMA))
+        + gnark_g1_compressed(g1(DELTA))
+        + gnark_g2_compressed(g2(DELTA))
+        + len(ic_points).to_bytes(4, "big")
+        + b"".join(gnark_g1_compressed(point) for point in ic_points)
+    )
+    proof_bin = gnark_g1_raw(proof[0]) + gnark_g2_raw(proof[1]) + gnark_g1_raw(proof[2])
+    with open(os.path.join(out, "gnark_vk.bin"), "wb") as f:
+        f.write(vk_bin)
+    with open(os.path.join(out, "gnark_proof.bin"), "wb") as f:
+        f.write(proof_bin)
+
+
+if __name__ == "__main__":
+    main()